@@ -0,0 +1,21 @@
+// run with cargo bench --features bench
+use kk_browser_lib::bench;
+
+const PAGE_SIZE: usize = 500;
+const PAGES: usize = 20;
+
+fn main() {
+    for count in [10_000, 50_000, 100_000] {
+        let timings = bench::get_presets(count, PAGE_SIZE, PAGES);
+
+        println!(
+            "{} presets, {} pages of {}: cloning every match {:?}, paging over ids {:?} ({:.1}x)",
+            count,
+            PAGES,
+            PAGE_SIZE,
+            timings.cloning,
+            timings.ids,
+            timings.cloning.as_secs_f64() / timings.ids.as_secs_f64()
+        );
+    }
+}
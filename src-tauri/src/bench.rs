@@ -0,0 +1,94 @@
+// entry points for benches/, which can't reach the private modules themselves
+// only built with the bench feature, so none of it ends up in the app
+use crate::{
+    intern::Interner,
+    preset::{Preset, PresetStatus},
+    product::ProductKey,
+    query::{PresetQuery, SearchIndex, paginate},
+};
+use ordered_hash_map::OrderedHashMap;
+use std::{
+    collections::HashSet,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+pub struct PaginationTimings {
+    // every match copied before skipping to the page, as get_presets used to do
+    pub cloning: Duration,
+    // filtering into ids and copying the page only
+    pub ids: Duration,
+}
+
+fn build_presets(count: usize) -> OrderedHashMap<usize, Preset> {
+    let mut interner = Interner::default();
+    let mut presets: OrderedHashMap<usize, Preset> = OrderedHashMap::new();
+
+    for id in 1..=count {
+        let product = id % 200;
+
+        presets.insert(
+            id,
+            Preset {
+                name: format!("Preset {}", id),
+                vendor: interner.intern(&format!("Vendor {}", product % 20)),
+                vendor_id: product % 20 + 1,
+                comment: "A preset with a comment of usual length".into(),
+                product_id: ProductKey::Id(product),
+                product_name: interner.intern(&format!("Product {}", product)),
+                id,
+                file_name: format!("/Library/Product {}/Preset {}.nksf", product, id).into(),
+                categories: HashSet::from([id % 30 + 1, id % 7 + 31]),
+                modes: HashSet::from([id % 40 + 1]),
+                bank: id % 50,
+                status: PresetStatus::Ok,
+                source: 0,
+            },
+        );
+    }
+
+    presets
+}
+
+// pages through a query matching every preset, the first `pages` pages of `page_size` each
+pub fn get_presets(count: usize, page_size: usize, pages: usize) -> PaginationTimings {
+    let presets = build_presets(count);
+    let index = SearchIndex::new(presets.values());
+    let query = PresetQuery {
+        include_broken: true,
+        ..Default::default()
+    };
+
+    let start = Instant::now();
+
+    for page in 0..pages {
+        let matches = presets
+            .values()
+            .filter(|p| query.matches(p) && index.contains(p.id, query.text))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        black_box(
+            matches
+                .iter()
+                .skip(page * page_size)
+                .take(page_size)
+                .cloned()
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    let cloning = start.elapsed();
+    let start = Instant::now();
+
+    for page in 0..pages {
+        let ids = query.filter(&presets, &index);
+
+        black_box(paginate(&presets, &ids, page * page_size, page_size));
+    }
+
+    PaginationTimings {
+        cloning,
+        ids: start.elapsed(),
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{
+    category::{Bank, Category, Mode},
+    library::Library,
+    load_report::LoadReport,
+    preset::{Preset, PresetStatus},
+    product::{Product, ProductKey},
+    source::{SourceInfo, SourceStamp},
+};
+use multi_key_map::MultiKeyMap;
+use ordered_hash_map::OrderedHashMap;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+// bump whenever the layout below changes, older caches are thrown away
+const CACHE_VERSION: u32 = 4;
+
+// products and presets skip fields when sent to the frontend, so they get their own layout here
+#[derive(Serialize, Deserialize)]
+struct CachedProduct {
+    id: usize,
+    name: String,
+    content_dir: String,
+    vendor: String,
+    upid: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedPreset {
+    name: String,
+    vendor: String,
+    comment: String,
+    product_id: ProductKey,
+    product_name: String,
+    id: usize,
+    file_name: PathBuf,
+    categories: HashSet<usize>,
+    modes: HashSet<usize>,
+    bank: usize,
+    source: usize,
+}
+
+// the loaded library, stored on disk to show it right away on the next start
+#[derive(Serialize, Deserialize)]
+pub struct LibraryCache {
+    version: u32,
+    // None if at least one source can't tell whether it changed
+    stamps: Option<Vec<SourceStamp>>,
+    sources: Vec<SourceInfo>,
+    reports: Vec<LoadReport>,
+    banks: Vec<Bank>,
+    categories: Vec<Category>,
+    modes: Vec<Mode>,
+    products: Vec<CachedProduct>,
+    presets: Vec<CachedPreset>,
+}
+
+impl LibraryCache {
+    pub fn new(
+        stamps: Option<Vec<SourceStamp>>,
+        library: &Library,
+        sources: &[SourceInfo],
+        reports: &[LoadReport],
+    ) -> Self {
+        Self {
+            version: CACHE_VERSION,
+            stamps,
+            sources: sources.to_vec(),
+            reports: reports.to_vec(),
+            banks: library.banks.values().cloned().collect::<Vec<_>>(),
+            categories: library.categories.values().cloned().collect::<Vec<_>>(),
+            modes: library.modes.values().cloned().collect::<Vec<_>>(),
+            products: library
+                .products
+                .values()
+                .map(|p| CachedProduct {
+                    id: p.id,
+                    name: p.name.clone(),
+                    content_dir: p.content_dir.clone(),
+                    vendor: p.vendor.to_string(),
+                    upid: p.upid.clone(),
+                })
+                .collect::<Vec<_>>(),
+            presets: library
+                .presets
+                .values()
+                .map(|p| CachedPreset {
+                    name: p.name.clone(),
+                    vendor: p.vendor.to_string(),
+                    comment: p.comment.clone(),
+                    product_id: p.product_id.clone(),
+                    product_name: p.product_name.to_string(),
+                    id: p.id,
+                    file_name: p.file_name.clone(),
+                    categories: p.categories.clone(),
+                    modes: p.modes.clone(),
+                    bank: p.bank,
+                    source: p.source,
+                })
+                .collect::<Vec<_>>(),
+        }
+    }
+
+    pub fn load(cache_dir: &Path) -> Option<Self> {
+        let file = File::open(cache_dir.join("library.json")).ok()?;
+        let cache: Self = serde_json::from_reader(BufReader::new(file)).ok()?;
+
+        if cache.version == CACHE_VERSION {
+            Some(cache)
+        } else {
+            None
+        }
+    }
+
+    pub fn save(&self, cache_dir: &Path) {
+        if fs::create_dir_all(cache_dir).is_ok()
+            && let Ok(file) = File::create(cache_dir.join("library.json"))
+        {
+            let _ = serde_json::to_writer(BufWriter::new(file), self);
+        }
+    }
+
+    pub fn is_current(&self, stamps: &Option<Vec<SourceStamp>>) -> bool {
+        stamps.is_some() && self.stamps == *stamps
+    }
+
+    pub fn into_library(self) -> (Library, Vec<SourceInfo>, Vec<LoadReport>) {
+        let mut library = Library {
+            banks: OrderedHashMap::new(),
+            categories: OrderedHashMap::new(),
+            modes: OrderedHashMap::new(),
+            products: MultiKeyMap::new(),
+            presets: OrderedHashMap::new(),
+            warnings: vec![],
+            snapshot_time: None,
+        };
+
+        // preset memberships aren't stored twice, they are restored from the presets
+        self.banks.into_iter().for_each(|b| {
+            library.banks.insert(b.id, b);
+        });
+
+        self.categories.into_iter().for_each(|c| {
+            library.categories.insert(c.id, c);
+        });
+
+        self.modes.into_iter().for_each(|m| {
+            library.modes.insert(m.id, m);
+        });
+
+        for p in self.products {
+            let mut keys: Vec<ProductKey> = vec![ProductKey::Id(p.id)];
+
+            // only the first product with an upid owns it, just like when merging sources
+            if !p.upid.is_empty()
+                && !library
+                    .products
+                    .contains_key(&ProductKey::Upid(p.upid.clone()))
+            {
+                keys.push(ProductKey::Upid(p.upid.clone()));
+            }
+
+            library.products.insert_many(
+                keys,
+                Product {
+                    id: p.id,
+                    name: p.name,
+                    content_dir: p.content_dir,
+                    vendor: p.vendor.into(),
+                    vendor_id: 0,
+                    upid: p.upid,
+                    presets: HashSet::new(),
+                    info: None,
+                    preview_dir: None,
+                },
+            );
+        }
+
+        for p in self.presets {
+            if let Some(bank) = library.banks.get_mut(&p.bank) {
+                bank.presets.insert(p.id);
+            }
+
+            for c in p.categories.iter() {
+                if let Some(category) = library.categories.get_mut(c) {
+                    category.presets.insert(p.id);
+                }
+            }
+
+            for m in p.modes.iter() {
+                if let Some(mode) = library.modes.get_mut(m) {
+                    mode.presets.insert(p.id);
+                }
+            }
+
+            if let Some(product) = library.products.get_mut(&p.product_id) {
+                product.presets.insert(p.id);
+            }
+
+            library.presets.insert(
+                p.id,
+                Preset {
+                    name: p.name,
+                    vendor: p.vendor.into(),
+                    vendor_id: 0,
+                    comment: p.comment,
+                    product_id: p.product_id,
+                    product_name: p.product_name.into(),
+                    id: p.id,
+                    file_name: p.file_name,
+                    categories: p.categories,
+                    modes: p.modes,
+                    bank: p.bank,
+                    status: PresetStatus::Unknown,
+                    source: p.source,
+                },
+            );
+        }
+
+        (library, self.sources, self.reports)
+    }
+}
@@ -1,11 +1,14 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
     collections::HashSet,
     hash::{Hash, Hasher},
 };
 
-#[derive(Clone, Serialize)]
+// id of the pseudo entries collecting presets without a bank, type or characteristic
+pub const NO_ENTRY: usize = 0;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Category {
     pub id: usize,
     pub name: String,
@@ -57,7 +60,7 @@ impl Hash for Category {
     }
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Mode {
     pub id: usize,
     pub name: String,
@@ -91,7 +94,7 @@ impl Hash for Mode {
     }
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Bank {
     pub id: usize,
     pub entry1: String,
@@ -0,0 +1,604 @@
+use crate::{
+    category::{Bank, Category, Mode},
+    library::Library,
+    load_report::LoadWarning,
+    preset::{Preset, PresetStatus},
+    product::{Product, ProductKey},
+    progress::{LoadingPhase, Progress, ProgressCallback},
+    schema,
+    source::{LibrarySource, SourceStamp},
+};
+use multi_key_map::MultiKeyMap;
+use ordered_hash_map::OrderedHashMap;
+use rusqlite::{
+    Connection, ErrorCode, OpenFlags, Row,
+    backup::{Backup, StepResult},
+    types::FromSql,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// Komplete Kontrol may hold the database for a while during a rescan
+const SNAPSHOT_ATTEMPTS: u32 = 6;
+const SNAPSHOT_BACKOFF: Duration = Duration::from_millis(250);
+
+#[derive(Debug)]
+pub enum Db3Error {
+    // names of the missing tables and columns
+    UnsupportedSchema(Vec<String>),
+    Sqlite(rusqlite::Error),
+    // still locked after all attempts
+    Busy,
+}
+
+impl fmt::Display for Db3Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Db3Error::UnsupportedSchema(missing) => write!(
+                f,
+                "unsupported database schema, missing {}",
+                missing.join(", ")
+            ),
+            Db3Error::Sqlite(e) => write!(f, "unable to read database: {}", e),
+            Db3Error::Busy => write!(f, "database is locked by another application"),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for Db3Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Db3Error::Sqlite(e)
+    }
+}
+
+pub struct Db3Source {
+    pub path: PathBuf,
+}
+
+impl LibrarySource for Db3Source {
+    fn get_name(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+
+    fn load(&self, progress: ProgressCallback) -> Result<Option<Library>, String> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+
+        let (conn, snapshot_time) = snapshot(&self.path).map_err(|e| e.to_string())?;
+
+        load(&conn, progress)
+            .map(|library| {
+                Some(Library {
+                    snapshot_time: Some(snapshot_time),
+                    ..library
+                })
+            })
+            .map_err(|e| e.to_string())
+    }
+
+    fn get_stamp(&self) -> Option<SourceStamp> {
+        let name = self.get_name();
+
+        // a missing database is a valid state as well
+        if !self.path.exists() {
+            return Some(SourceStamp {
+                name,
+                ..SourceStamp::default()
+            });
+        }
+
+        let mut stamp = SourceStamp {
+            name,
+            schema: open(&self.path)?
+                .query_row("PRAGMA schema_version", [], |row| row.get::<_, i64>(0))
+                .ok()?,
+            ..SourceStamp::default()
+        };
+
+        // recent changes may only live in the write-ahead log yet
+        let mut wal = self.path.clone().into_os_string();
+
+        wal.push("-wal");
+
+        for path in [self.path.clone(), PathBuf::from(wal)] {
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+
+            stamp.size += metadata.len();
+            stamp.modified = stamp.modified.max(
+                metadata
+                    .modified()
+                    .ok()?
+                    .duration_since(UNIX_EPOCH)
+                    .ok()?
+                    .as_secs(),
+            );
+        }
+
+        Some(stamp)
+    }
+}
+
+fn is_busy(e: &rusqlite::Error) -> bool {
+    matches!(
+        e.sqlite_error_code(),
+        Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+    )
+}
+
+fn try_snapshot(source: &Connection, target: &mut Connection) -> Result<bool, rusqlite::Error> {
+    // a single step copies everything within one read transaction, including the write-ahead log
+    match Backup::new(source, target)?.step(-1) {
+        Ok(StepResult::Done) => Ok(true),
+        Ok(_) => Ok(false),
+        Err(e) if is_busy(&e) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+// copies a consistent state of the database into memory, so Komplete Kontrol can keep writing while we load
+// returns the copy and the time it reflects, in seconds since the epoch
+pub fn snapshot(path: &Path) -> Result<(Connection, u64), Db3Error> {
+    let source =
+        open(path).ok_or_else(|| Db3Error::Sqlite(rusqlite::Error::InvalidPath(path.into())))?;
+    let mut target = Connection::open_in_memory()?;
+    let mut backoff = SNAPSHOT_BACKOFF;
+
+    for _ in 0..SNAPSHOT_ATTEMPTS {
+        if try_snapshot(&source, &mut target)? {
+            let time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+
+            return Ok((target, time));
+        }
+
+        thread::sleep(backoff);
+        backoff *= 2;
+    }
+
+    Err(Db3Error::Busy)
+}
+
+fn open(path: &Path) -> Option<Connection> {
+    Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY
+            | OpenFlags::SQLITE_OPEN_URI
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .ok()
+}
+
+fn count(conn: &Connection, table: &str) -> usize {
+    conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+        row.get::<usize, usize>(0)
+    })
+    .unwrap_or(0)
+}
+
+// reads a column which has to be set, NULLs and unreadable values are reported so the row can be skipped
+fn get_required<T: FromSql>(
+    row: &Row,
+    column: usize,
+    table: &str,
+    id: Option<usize>,
+    warnings: &mut Vec<LoadWarning>,
+) -> Option<T> {
+    match row.get::<usize, T>(column) {
+        Ok(value) => Some(value),
+        Err(_) => {
+            warnings.push(LoadWarning::InvalidValue {
+                table: table.into(),
+                column: row.as_ref().column_name(column).unwrap_or_default().into(),
+                id,
+            });
+            None
+        }
+    }
+}
+
+pub fn load(conn: &Connection, progress: ProgressCallback) -> Result<Library, Db3Error> {
+    let version = schema::detect(conn)?;
+    let mut warnings: Vec<LoadWarning> = vec![];
+
+    if !version.get_absent_columns().is_empty() {
+        warnings.push(LoadWarning::OlderSchema {
+            missing: version
+                .get_absent_columns()
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>(),
+        });
+    }
+
+    let mut banks_progress =
+        Progress::new(progress, LoadingPhase::Banks, count(conn, "k_bank_chain"));
+
+    let mut banks: OrderedHashMap<usize, Bank> = OrderedHashMap::new();
+    let mut b: Vec<Bank> = vec![];
+
+    let mut stmt =
+        conn.prepare(&version.select("k_bank_chain", &["id", "entry1", "entry2", "entry3"]))?;
+
+    let mut rows = stmt.query([])?;
+
+    while let Some(row) = rows.next()? {
+        banks_progress.step();
+
+        let Some(id) = get_required::<usize>(row, 0, "k_bank_chain", None, &mut warnings) else {
+            continue;
+        };
+        let Some(entry1) = get_required::<String>(row, 1, "k_bank_chain", Some(id), &mut warnings)
+        else {
+            continue;
+        };
+
+        b.push(Bank {
+            id,
+            entry1,
+            entry2: row.get::<usize, String>(2).unwrap_or("".into()),
+            entry3: row.get::<usize, String>(3).unwrap_or("".into()),
+            presets: HashSet::new(),
+        });
+    }
+
+    drop(rows);
+
+    banks_progress.finish();
+
+    b.sort();
+
+    b.into_iter().for_each(|b| {
+        banks.insert(b.id, b);
+    });
+
+    let mut products: MultiKeyMap<ProductKey, Product> = MultiKeyMap::new();
+
+    let mut content_paths_progress = Progress::new(
+        progress,
+        LoadingPhase::ContentPaths,
+        count(conn, "k_content_path"),
+    );
+
+    let mut map: HashMap<usize, (String, String, String)> = HashMap::new();
+    let mut stmt =
+        conn.prepare(&version.select("k_content_path", &["id", "path", "alias", "upid"]))?;
+
+    let mut rows = stmt.query([])?;
+
+    while let Some(row) = rows.next()? {
+        content_paths_progress.step();
+
+        let Some(id) = get_required::<usize>(row, 0, "k_content_path", None, &mut warnings) else {
+            continue;
+        };
+        let Some(path) = get_required::<String>(row, 1, "k_content_path", Some(id), &mut warnings)
+        else {
+            continue;
+        };
+
+        map.insert(
+            id,
+            (
+                path,
+                row.get::<usize, String>(2).unwrap_or("".into()),
+                row.get::<usize, String>(3).unwrap_or("".into()),
+            ),
+        );
+    }
+
+    drop(rows);
+
+    content_paths_progress.finish();
+
+    let cmd = format!(
+        "{} GROUP BY content_path_id, vendor",
+        version.select("k_sound_info", &["content_path_id", "vendor"])
+    );
+
+    stmt = conn.prepare(&cmd)?;
+
+    let mut rows = stmt.query([])?;
+
+    while let Some(row) = rows.next()? {
+        // presets with broken content paths are reported below
+        let Ok(id) = row.get::<usize, usize>(0) else {
+            continue;
+        };
+
+        let Some((path, alias, upid)) = map.get(&id) else {
+            continue;
+        };
+
+        if products.contains_key(&ProductKey::Id(id)) {
+            continue;
+        }
+
+        let keys: Vec<ProductKey> = match upid.as_str() {
+            "" => vec![ProductKey::Id(id)],
+            other => vec![ProductKey::Id(id), ProductKey::Upid(other.to_string())],
+        };
+
+        products.insert_many(
+            keys,
+            Product {
+                id,
+                name: alias.clone(),
+                vendor: row.get::<usize, String>(1).unwrap_or_default().into(),
+                vendor_id: 0,
+                content_dir: path.clone(),
+                upid: upid.clone(),
+                presets: HashSet::new(),
+                info: None,
+                preview_dir: None,
+            },
+        );
+    }
+
+    drop(rows);
+
+    let mut presets: OrderedHashMap<usize, Preset> = OrderedHashMap::new();
+    let mut presets_progress =
+        Progress::new(progress, LoadingPhase::Presets, count(conn, "k_sound_info"));
+
+    let cmd = version.select(
+        "k_sound_info",
+        &[
+            "id",
+            "name",
+            "vendor",
+            "comment",
+            "content_path_id",
+            "file_name",
+            "bank_chain_id",
+        ],
+    );
+
+    let mut stmt = conn.prepare(&cmd)?;
+
+    let mut p: Vec<Preset> = vec![];
+    let mut rows = stmt.query([])?;
+
+    while let Some(row) = rows.next()? {
+        presets_progress.step();
+
+        let Some(id) = get_required::<usize>(row, 0, "k_sound_info", None, &mut warnings) else {
+            continue;
+        };
+        let Some(content_path) =
+            get_required::<usize>(row, 4, "k_sound_info", Some(id), &mut warnings)
+        else {
+            continue;
+        };
+        let Some(file_name) =
+            get_required::<String>(row, 5, "k_sound_info", Some(id), &mut warnings)
+        else {
+            continue;
+        };
+
+        let Some(product) = products.get(&ProductKey::Id(content_path)) else {
+            warnings.push(LoadWarning::MissingContentPath {
+                preset: id,
+                content_path,
+            });
+            continue;
+        };
+
+        let file_name = PathBuf::from(&file_name);
+
+        let name = match row.get::<usize, String>(1) {
+            Ok(name) => name,
+            Err(_) => {
+                warnings.push(LoadWarning::NullName { preset: id });
+                file_name
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            }
+        };
+
+        let mut bank = row.get::<usize, usize>(6).unwrap_or(0);
+
+        if bank != 0 && !banks.contains_key(&bank) {
+            warnings.push(LoadWarning::UnknownBank { preset: id, bank });
+            bank = 0;
+        }
+
+        p.push(Preset {
+            id,
+            name,
+            vendor: row.get::<usize, String>(2).unwrap_or_default().into(),
+            vendor_id: 0,
+            comment: row.get::<usize, String>(3).unwrap_or("".into()),
+            product_id: ProductKey::Id(content_path),
+            product_name: product.name.as_str().into(),
+            file_name,
+            categories: HashSet::new(),
+            modes: HashSet::new(),
+            bank,
+            status: PresetStatus::Unknown,
+            source: 0,
+        });
+    }
+
+    drop(rows);
+
+    presets_progress.finish();
+
+    p.sort();
+
+    p.into_iter().for_each(|p| {
+        if let Some(product) = products.get_mut(&p.product_id) {
+            product.presets.insert(p.id);
+        }
+        if let Some(bank) = banks.get_mut(&p.bank) {
+            bank.presets.insert(p.id);
+        }
+        presets.insert(p.id, p);
+    });
+
+    let mut categories: OrderedHashMap<usize, Category> = OrderedHashMap::new();
+    let mut categories_progress = Progress::new(
+        progress,
+        LoadingPhase::Categories,
+        count(conn, "k_category") + count(conn, "k_sound_info_category"),
+    );
+
+    let mut stmt = conn.prepare(&version.select(
+        "k_category",
+        &["id", "category", "subcategory", "subsubcategory"],
+    ))?;
+
+    let mut c: Vec<Category> = vec![];
+    let mut rows = stmt.query([])?;
+
+    while let Some(row) = rows.next()? {
+        categories_progress.step();
+
+        let Some(id) = get_required::<usize>(row, 0, "k_category", None, &mut warnings) else {
+            continue;
+        };
+        let Some(name) = get_required::<String>(row, 1, "k_category", Some(id), &mut warnings)
+        else {
+            continue;
+        };
+
+        c.push(Category {
+            id,
+            name,
+            subcategory: row.get::<usize, String>(2).unwrap_or("".into()),
+            subsubcategory: row.get::<usize, String>(3).unwrap_or("".into()),
+            presets: HashSet::new(),
+        });
+    }
+
+    drop(rows);
+
+    c.sort();
+
+    c.into_iter().for_each(|c| {
+        categories.insert(c.id, c);
+    });
+
+    let mut stmt =
+        conn.prepare(&version.select("k_sound_info_category", &["sound_info_id", "category_id"]))?;
+
+    let mut rows = stmt.query([])?;
+
+    while let Some(row) = rows.next()? {
+        categories_progress.step();
+
+        let table = "k_sound_info_category";
+        let Some(preset) = get_required::<usize>(row, 0, table, None, &mut warnings) else {
+            continue;
+        };
+        let Some(category) = get_required::<usize>(row, 1, table, None, &mut warnings) else {
+            continue;
+        };
+
+        match (presets.get_mut(&preset), categories.get_mut(&category)) {
+            (Some(p), Some(c)) => {
+                c.presets.insert(preset);
+                p.categories.insert(category);
+            }
+            _ => warnings.push(LoadWarning::OrphanJoin {
+                table: table.into(),
+                preset,
+                entry: category,
+            }),
+        }
+    }
+
+    drop(rows);
+
+    categories_progress.finish();
+
+    let mut modes: OrderedHashMap<usize, Mode> = OrderedHashMap::new();
+    let mut modes_progress = Progress::new(
+        progress,
+        LoadingPhase::Modes,
+        count(conn, "k_mode") + count(conn, "k_sound_info_mode"),
+    );
+
+    let mut stmt = conn.prepare(&version.select("k_mode", &["id", "name"]))?;
+
+    let mut m: Vec<Mode> = vec![];
+    let mut rows = stmt.query([])?;
+
+    while let Some(row) = rows.next()? {
+        modes_progress.step();
+
+        let Some(id) = get_required::<usize>(row, 0, "k_mode", None, &mut warnings) else {
+            continue;
+        };
+        let Some(name) = get_required::<String>(row, 1, "k_mode", Some(id), &mut warnings) else {
+            continue;
+        };
+
+        m.push(Mode {
+            id,
+            name,
+            presets: HashSet::new(),
+        });
+    }
+
+    drop(rows);
+
+    m.sort();
+
+    m.into_iter().for_each(|m| {
+        modes.insert(m.id, m);
+    });
+
+    let mut stmt =
+        conn.prepare(&version.select("k_sound_info_mode", &["sound_info_id", "mode_id"]))?;
+
+    let mut rows = stmt.query([])?;
+
+    while let Some(row) = rows.next()? {
+        modes_progress.step();
+
+        let table = "k_sound_info_mode";
+        let Some(preset) = get_required::<usize>(row, 0, table, None, &mut warnings) else {
+            continue;
+        };
+        let Some(mode) = get_required::<usize>(row, 1, table, None, &mut warnings) else {
+            continue;
+        };
+
+        match (presets.get_mut(&preset), modes.get_mut(&mode)) {
+            (Some(p), Some(m)) => {
+                m.presets.insert(preset);
+                p.modes.insert(mode);
+            }
+            _ => warnings.push(LoadWarning::OrphanJoin {
+                table: table.into(),
+                preset,
+                entry: mode,
+            }),
+        }
+    }
+
+    drop(rows);
+
+    modes_progress.finish();
+
+    Ok(Library {
+        banks,
+        categories,
+        modes,
+        products,
+        presets,
+        warnings,
+        snapshot_time: None,
+    })
+}
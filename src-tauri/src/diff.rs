@@ -0,0 +1,271 @@
+use crate::{library::Library, product::Product};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    path::PathBuf,
+};
+
+#[derive(Serialize)]
+pub struct Rename {
+    pub key: String,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Default, Serialize)]
+pub struct Changes {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub renamed: Vec<Rename>,
+}
+
+#[derive(Serialize)]
+pub struct PresetChange {
+    pub file_name: PathBuf,
+    pub name: String,
+    pub added_categories: Vec<String>,
+    pub removed_categories: Vec<String>,
+    pub added_modes: Vec<String>,
+    pub removed_modes: Vec<String>,
+}
+
+#[derive(Default, Serialize)]
+pub struct LibraryDiff {
+    pub presets: Changes,
+    pub products: Changes,
+    pub banks: Changes,
+    pub categories: Changes,
+    pub changed_presets: Vec<PresetChange>,
+}
+
+// compares two sets of entries by their stable key, an entry whose key stays but name changes is a rename
+fn compare(old: BTreeMap<String, String>, new: BTreeMap<String, String>) -> Changes {
+    let mut changes = Changes::default();
+
+    for (key, name) in old.iter() {
+        match new.get(key) {
+            None => changes.removed.push(name.clone()),
+            Some(new_name) if new_name != name => changes.renamed.push(Rename {
+                key: key.clone(),
+                old: name.clone(),
+                new: new_name.clone(),
+            }),
+            _ => {}
+        }
+    }
+
+    changes.added = new
+        .iter()
+        .filter(|(key, _)| !old.contains_key(*key))
+        .map(|(_, name)| name.clone())
+        .collect::<Vec<_>>();
+
+    changes
+}
+
+// row ids differ between machines and after Komplete Kontrol rebuilt its database, so nothing is keyed by them
+// parts of a compound key are joined with a character no name contains
+const KEY_SEPARATOR: &str = "\u{1f}";
+
+// the upid survives renames of the alias, products without one are told apart by their content path,
+// as several of them may share an alias
+fn product_key(product: &Product) -> String {
+    if !product.upid.is_empty() {
+        product.upid.clone()
+    } else if !product.content_dir.is_empty() {
+        product.content_dir.replace('\\', "/").to_lowercase()
+    } else {
+        product.name.clone()
+    }
+}
+
+fn product_keys(library: &Library) -> BTreeMap<String, String> {
+    library
+        .products
+        .values()
+        .map(|p| (product_key(p), p.name.clone()))
+        .collect::<BTreeMap<_, _>>()
+}
+
+// a bank is listed once per product it holds presets of, a renamed chain shows up as removed and added
+fn bank_keys(library: &Library) -> BTreeMap<String, String> {
+    let mut keys: BTreeMap<String, String> = BTreeMap::new();
+
+    for bank in library.banks.values() {
+        let products = bank
+            .presets
+            .iter()
+            .filter_map(|p| library.presets.get(p))
+            .filter_map(|p| library.products.get(&p.product_id))
+            .map(|p| (product_key(p), p.name.clone()))
+            .collect::<BTreeMap<_, _>>();
+        let chain = [
+            bank.entry1.as_str(),
+            bank.entry2.as_str(),
+            bank.entry3.as_str(),
+        ]
+        .join(KEY_SEPARATOR);
+
+        if products.is_empty() {
+            keys.insert(format!("{}{}", KEY_SEPARATOR, chain), bank.get_name());
+        }
+
+        for (product, name) in products {
+            keys.insert(
+                format!("{}{}{}", product, KEY_SEPARATOR, chain),
+                format!("{}: {}", name, bank.get_name()),
+            );
+        }
+    }
+
+    keys
+}
+
+// the whole chain is the key, a renamed chain shows up as removed and added
+fn category_keys(library: &Library) -> BTreeMap<String, String> {
+    library
+        .categories
+        .values()
+        .map(|c| {
+            (
+                [
+                    c.name.as_str(),
+                    c.subcategory.as_str(),
+                    c.subsubcategory.as_str(),
+                ]
+                .join(KEY_SEPARATOR),
+                c.get_name(),
+            )
+        })
+        .collect::<BTreeMap<_, _>>()
+}
+
+// category and mode names per preset file
+fn preset_tags(
+    library: &Library,
+) -> BTreeMap<PathBuf, (String, BTreeSet<String>, BTreeSet<String>)> {
+    library
+        .presets
+        .values()
+        .map(|p| {
+            (
+                p.file_name.clone(),
+                (
+                    p.name.clone(),
+                    p.categories
+                        .iter()
+                        .filter_map(|c| library.categories.get(c).map(|c| c.get_name()))
+                        .collect::<BTreeSet<_>>(),
+                    p.modes
+                        .iter()
+                        .filter_map(|m| library.modes.get(m).map(|m| m.name.clone()))
+                        .collect::<BTreeSet<_>>(),
+                ),
+            )
+        })
+        .collect::<BTreeMap<_, _>>()
+}
+
+impl LibraryDiff {
+    pub fn new(old: &Library, new: &Library) -> Self {
+        let names = |library: &Library| {
+            library
+                .presets
+                .values()
+                .map(|p| (p.file_name.to_string_lossy().into_owned(), p.name.clone()))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        let old_tags = preset_tags(old);
+        let new_tags = preset_tags(new);
+
+        let changed_presets = old_tags
+            .iter()
+            .filter_map(|(file_name, (_, old_categories, old_modes))| {
+                let (name, new_categories, new_modes) = new_tags.get(file_name)?;
+
+                if old_categories == new_categories && old_modes == new_modes {
+                    return None;
+                }
+
+                Some(PresetChange {
+                    file_name: file_name.clone(),
+                    name: name.clone(),
+                    added_categories: new_categories.difference(old_categories).cloned().collect(),
+                    removed_categories: old_categories
+                        .difference(new_categories)
+                        .cloned()
+                        .collect(),
+                    added_modes: new_modes.difference(old_modes).cloned().collect(),
+                    removed_modes: old_modes.difference(new_modes).cloned().collect(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            presets: compare(names(old), names(new)),
+            products: compare(product_keys(old), product_keys(new)),
+            banks: compare(bank_keys(old), bank_keys(new)),
+            categories: compare(category_keys(old), category_keys(new)),
+            changed_presets,
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        for (title, changes) in [
+            ("Presets", &self.presets),
+            ("Products", &self.products),
+            ("Banks", &self.banks),
+            ("Types", &self.categories),
+        ] {
+            let _ = writeln!(
+                text,
+                "{}: {} added, {} removed, {} renamed",
+                title,
+                changes.added.len(),
+                changes.removed.len(),
+                changes.renamed.len()
+            );
+
+            for added in changes.added.iter() {
+                let _ = writeln!(text, "  + {}", added);
+            }
+
+            for removed in changes.removed.iter() {
+                let _ = writeln!(text, "  - {}", removed);
+            }
+
+            for renamed in changes.renamed.iter() {
+                let _ = writeln!(text, "  ~ {} -> {}", renamed.old, renamed.new);
+            }
+
+            text.push('\n');
+        }
+
+        let _ = writeln!(
+            text,
+            "Presets with changed types or characteristics: {}",
+            self.changed_presets.len()
+        );
+
+        for change in self.changed_presets.iter() {
+            let _ = writeln!(text, "  {} ({})", change.name, change.file_name.display());
+
+            for (sign, entries) in [
+                ("+ type", &change.added_categories),
+                ("- type", &change.removed_categories),
+                ("+ characteristic", &change.added_modes),
+                ("- characteristic", &change.removed_modes),
+            ] {
+                for entry in entries.iter() {
+                    let _ = writeln!(text, "    {} {}", sign, entry);
+                }
+            }
+        }
+
+        text
+    }
+}
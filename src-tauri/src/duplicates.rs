@@ -0,0 +1,154 @@
+use crate::{
+    preset::Preset,
+    product::{Product, ProductKey},
+};
+use multi_key_map::MultiKeyMap;
+use serde::Serialize;
+use std::{
+    collections::{HashMap, hash_map::DefaultHasher},
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateReason {
+    // same vendor and (nearly) the same name, nothing else matched
+    Name,
+    // byte-identical preset files, whatever they are called
+    Content,
+    // byte-identical previews
+    Preview,
+}
+
+#[derive(Serialize)]
+pub struct DuplicateGroup {
+    pub name: String,
+    pub vendor: String,
+    pub reason: DuplicateReason,
+    pub presets: Vec<usize>,
+    pub file_names: Vec<PathBuf>,
+}
+
+// lowercases the name and drops punctuation as well as trailing copy markers like "2" or "copy"
+fn normalize_name(name: &str) -> String {
+    let mut tokens: Vec<String> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_lowercase())
+        .collect::<Vec<_>>();
+
+    while tokens.len() > 1
+        && tokens
+            .last()
+            .is_some_and(|t| t == "copy" || t.chars().all(|c| c.is_ascii_digit()))
+    {
+        tokens.pop();
+    }
+
+    tokens.join(" ")
+}
+
+fn hash_file(path: &Path) -> Option<u64> {
+    let mut hasher = DefaultHasher::new();
+
+    fs::read(path).ok()?.hash(&mut hasher);
+
+    Some(hasher.finish())
+}
+
+// splits the presets into groups sharing the same key, returns the groups and everything left over
+fn split_by<K: Eq + Hash>(
+    presets: Vec<&Preset>,
+    key: impl Fn(&Preset) -> Option<K>,
+) -> (Vec<Vec<&Preset>>, Vec<&Preset>) {
+    let mut groups: HashMap<K, Vec<&Preset>> = HashMap::new();
+    let mut rest: Vec<&Preset> = vec![];
+
+    for preset in presets {
+        match key(preset) {
+            Some(k) => groups.entry(k).or_default().push(preset),
+            None => rest.push(preset),
+        }
+    }
+
+    let mut matched: Vec<Vec<&Preset>> = vec![];
+
+    for group in groups.into_values() {
+        if group.len() > 1 {
+            matched.push(group);
+        } else {
+            rest.extend(group);
+        }
+    }
+
+    (matched, rest)
+}
+
+// splits the presets into groups of byte-identical files, only files sharing their size get read
+fn split_by_content(
+    presets: Vec<&Preset>,
+    path: impl Fn(&Preset) -> Option<PathBuf>,
+) -> (Vec<Vec<&Preset>>, Vec<&Preset>) {
+    let (by_size, mut rest) = split_by(presets, |p| fs::metadata(path(p)?).ok().map(|m| m.len()));
+    let mut matched: Vec<Vec<&Preset>> = vec![];
+
+    for group in by_size {
+        let (by_hash, left) = split_by(group, |p| hash_file(&path(p)?));
+
+        matched.extend(by_hash);
+        rest.extend(left);
+    }
+
+    (matched, rest)
+}
+
+pub fn find_duplicates<'a>(
+    presets: impl Iterator<Item = &'a Preset>,
+    products: &MultiKeyMap<ProductKey, Product>,
+) -> Vec<DuplicateGroup> {
+    let (by_content, rest) =
+        split_by_content(presets.collect::<Vec<_>>(), |p| Some(p.file_name.clone()));
+    let (by_preview, rest) =
+        split_by_content(rest, |p| p.get_preview_path(products.get(&p.product_id)?));
+    let (by_name, _) = split_by(rest, |p| {
+        Some((p.vendor.to_lowercase(), normalize_name(&p.name)))
+    });
+
+    let mut groups: Vec<(DuplicateReason, Vec<&Preset>)> = vec![];
+
+    groups.extend(
+        by_content
+            .into_iter()
+            .map(|g| (DuplicateReason::Content, g)),
+    );
+    groups.extend(
+        by_preview
+            .into_iter()
+            .map(|g| (DuplicateReason::Preview, g)),
+    );
+    groups.extend(by_name.into_iter().map(|g| (DuplicateReason::Name, g)));
+
+    let mut duplicates = groups
+        .into_iter()
+        .map(|(reason, mut group)| {
+            group.sort();
+
+            DuplicateGroup {
+                name: group[0].name.clone(),
+                vendor: group[0].vendor.to_string(),
+                reason,
+                presets: group.iter().map(|p| p.id).collect::<Vec<_>>(),
+                file_names: group
+                    .iter()
+                    .map(|p| p.file_name.clone())
+                    .collect::<Vec<_>>(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    duplicates.sort_by(|a, b| natord::compare_ignore_case(&a.name, &b.name));
+
+    duplicates
+}
@@ -0,0 +1,285 @@
+use crate::loudness::Loudness;
+use rodio::{Decoder, Source};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    f32::consts::PI,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+const FRAME_SIZE: usize = 1024;
+const MAX_FRAMES: usize = 32;
+// energy above this frequency counts towards the brightness
+const BRIGHTNESS_CUTOFF: f32 = 1500.0;
+const MIN_PITCH: f32 = 50.0;
+const MAX_PITCH: f32 = 1000.0;
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct AudioFeatures {
+    // spectral centroid in Hz
+    pub centroid: f32,
+    // share of spectral energy above BRIGHTNESS_CUTOFF, 0 to 1
+    pub brightness: f32,
+    // attack time in seconds
+    pub attack: f32,
+    // RMS level in dBFS
+    pub loudness: f32,
+    // estimated pitch as MIDI note number, None for unpitched sounds
+    pub pitch: Option<f32>,
+}
+
+impl AudioFeatures {
+    pub fn extract<S: Source>(source: S) -> Option<Self> {
+        let channels = (source.channels() as usize).max(1);
+        let sample_rate = source.sample_rate() as f32;
+        let samples: Vec<f32> = source.collect::<Vec<_>>();
+
+        // downmix to mono, the features don't care about the stereo image
+        let mono: Vec<f32> = samples
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+            .collect::<Vec<_>>();
+
+        if mono.len() < FRAME_SIZE || sample_rate <= 0.0 {
+            return None;
+        }
+
+        let loudness = Loudness::measure(mono.iter().copied())?;
+
+        let (centroid, brightness) = Self::spectral_features(&mono, sample_rate);
+        let envelope = Self::envelope(&mono, sample_rate);
+
+        Some(Self {
+            centroid,
+            brightness,
+            attack: Self::attack_time(&envelope, sample_rate),
+            // keep the value finite, so it survives the JSON round trip
+            loudness: loudness.rms.max(-120.0),
+            pitch: Self::estimate_pitch(&mono, &envelope, sample_rate),
+        })
+    }
+
+    // weighted distance between two feature sets, roughly in the range 0 to 2
+    pub fn distance(&self, other: &Self) -> f32 {
+        let centroid = (self.centroid.max(1.0).log2() - other.centroid.max(1.0).log2()) / 4.0;
+        let brightness = self.brightness - other.brightness;
+        let attack = ((self.attack + 0.001).log10() - (other.attack + 0.001).log10()) / 3.0;
+        let loudness = (self.loudness.max(-90.0) - other.loudness.max(-90.0)) / 30.0;
+        let pitch = match (self.pitch, other.pitch) {
+            (Some(a), Some(b)) => ((a - b).abs() / 24.0).min(1.0),
+            (None, None) => 0.0,
+            _ => 0.5,
+        };
+
+        (centroid * centroid
+            + brightness * brightness
+            + attack * attack
+            + loudness * loudness
+            + pitch * pitch)
+            .sqrt()
+    }
+
+    fn spectral_features(mono: &[f32], sample_rate: f32) -> (f32, f32) {
+        let frames = (mono.len() / FRAME_SIZE).min(MAX_FRAMES);
+        let hop = mono.len() / frames;
+        let bin_width = sample_rate / FRAME_SIZE as f32;
+
+        let mut weighted: f64 = 0.0;
+        let mut total: f64 = 0.0;
+        let mut bright: f64 = 0.0;
+
+        let window: Vec<f32> = (0..FRAME_SIZE)
+            .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f32 / FRAME_SIZE as f32).cos())
+            .collect::<Vec<_>>();
+        let twiddles: Vec<(f32, f32)> = (0..FRAME_SIZE)
+            .map(|n| {
+                let phase = 2.0 * PI * n as f32 / FRAME_SIZE as f32;
+                (phase.cos(), phase.sin())
+            })
+            .collect::<Vec<_>>();
+
+        for frame in 0..frames {
+            let frame: Vec<f32> = mono[frame * hop..frame * hop + FRAME_SIZE]
+                .iter()
+                .zip(&window)
+                .map(|(s, w)| s * w)
+                .collect::<Vec<_>>();
+
+            // plain DFT, previews are short and we only look at a handful of frames
+            for bin in 1..FRAME_SIZE / 2 {
+                let (mut re, mut im) = (0.0f32, 0.0f32);
+
+                for (n, s) in frame.iter().enumerate() {
+                    let (cos, sin) = twiddles[(bin * n) % FRAME_SIZE];
+
+                    re += s * cos;
+                    im -= s * sin;
+                }
+
+                let magnitude = (re * re + im * im).sqrt() as f64;
+                let frequency = bin as f32 * bin_width;
+
+                weighted += magnitude * frequency as f64;
+                total += magnitude;
+
+                if frequency >= BRIGHTNESS_CUTOFF {
+                    bright += magnitude;
+                }
+            }
+        }
+
+        if total <= 0.0 {
+            return (0.0, 0.0);
+        }
+
+        ((weighted / total) as f32, (bright / total) as f32)
+    }
+
+    // RMS envelope with a resolution of 10ms
+    fn envelope(mono: &[f32], sample_rate: f32) -> Vec<f32> {
+        let window = ((sample_rate / 100.0) as usize).max(1);
+
+        mono.chunks(window)
+            .map(|w| (w.iter().map(|s| s * s).sum::<f32>() / w.len() as f32).sqrt())
+            .collect::<Vec<_>>()
+    }
+
+    fn attack_time(envelope: &[f32], sample_rate: f32) -> f32 {
+        let window = ((sample_rate / 100.0) as usize).max(1) as f32 / sample_rate;
+        let peak = envelope.iter().copied().fold(0.0f32, f32::max);
+
+        if peak <= 0.0 {
+            return 0.0;
+        }
+
+        let start = envelope.iter().position(|e| *e >= peak * 0.1).unwrap_or(0);
+        let end = envelope.iter().position(|e| *e >= peak * 0.9).unwrap_or(0);
+
+        end.saturating_sub(start) as f32 * window
+    }
+
+    // autocorrelation based pitch estimate around the loudest part of the preview
+    fn estimate_pitch(mono: &[f32], envelope: &[f32], sample_rate: f32) -> Option<f32> {
+        let window = ((sample_rate / 100.0) as usize).max(1);
+        let size = FRAME_SIZE * 2;
+
+        let loudest = envelope
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i * window)
+            .unwrap_or(0);
+        let start = loudest.min(mono.len().saturating_sub(size));
+        let frame = &mono[start..(start + size).min(mono.len())];
+
+        let energy: f32 = frame.iter().map(|s| s * s).sum();
+
+        if energy <= 0.0 {
+            return None;
+        }
+
+        let min_lag = (sample_rate / MAX_PITCH) as usize;
+        let max_lag = ((sample_rate / MIN_PITCH) as usize).min(frame.len() / 2);
+
+        let (lag, correlation) = (min_lag.max(1)..max_lag)
+            .map(|lag| {
+                (
+                    lag,
+                    frame
+                        .iter()
+                        .zip(&frame[lag..])
+                        .map(|(a, b)| a * b)
+                        .sum::<f32>()
+                        / energy,
+                )
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+
+        if correlation < 0.5 {
+            return None;
+        }
+
+        Some(69.0 + 12.0 * (sample_rate / lag as f32 / 440.0).log2())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct FeatureEntry {
+    // modification time of the analyzed preview file
+    modified: u64,
+    features: AudioFeatures,
+}
+
+// features of all analyzed previews, keyed by the preset file they belong to
+#[derive(Default, Serialize, Deserialize)]
+pub struct FeatureStore {
+    entries: HashMap<PathBuf, FeatureEntry>,
+}
+
+impl FeatureStore {
+    pub fn load(cache_dir: &Path) -> Self {
+        File::open(cache_dir.join("features.json"))
+            .ok()
+            .and_then(|f| serde_json::from_reader(f).ok())
+            .unwrap_or_default()
+    }
+
+    // written next to the store and moved over it, so a crash while writing keeps the previous version
+    pub fn save(&self, cache_dir: &Path) {
+        let path = cache_dir.join("features.json");
+        let temp_path = cache_dir.join("features.json.tmp");
+
+        if fs::create_dir_all(cache_dir).is_ok()
+            && let Ok(file) = File::create(&temp_path)
+        {
+            let mut writer = BufWriter::new(file);
+
+            if serde_json::to_writer(&mut writer, self).is_ok()
+                && writer.flush().is_ok()
+                && writer.get_ref().sync_all().is_ok()
+            {
+                let _ = fs::rename(&temp_path, &path);
+            } else {
+                let _ = fs::remove_file(&temp_path);
+            }
+        }
+    }
+
+    pub fn get(&self, preset_file: &Path) -> Option<&AudioFeatures> {
+        self.entries.get(preset_file).map(|e| &e.features)
+    }
+
+    pub fn is_current(&self, preset_file: &Path, preview_path: &Path) -> bool {
+        self.entries
+            .get(preset_file)
+            .is_some_and(|e| Some(e.modified) == get_modified(preview_path))
+    }
+
+    pub fn insert(&mut self, preset_file: PathBuf, preview_path: &Path, features: AudioFeatures) {
+        self.entries.insert(
+            preset_file,
+            FeatureEntry {
+                modified: get_modified(preview_path).unwrap_or(0),
+                features,
+            },
+        );
+    }
+
+    pub fn analyze(preview_path: &Path) -> Option<AudioFeatures> {
+        AudioFeatures::extract(Decoder::try_from(File::open(preview_path).ok()?).ok()?)
+    }
+}
+
+fn get_modified(path: &Path) -> Option<u64> {
+    Some(
+        fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()?
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .ok()?
+            .as_secs(),
+    )
+}
@@ -0,0 +1,302 @@
+use crate::{
+    category::{Bank, Category, Mode},
+    library::Library,
+    load_report::LoadWarning,
+    nks::NksFileInfo,
+    preset::{Preset, PresetStatus},
+    product::{Product, ProductKey},
+    progress::{LoadingPhase, Progress, ProgressCallback},
+    source::{LibrarySource, SourceStamp},
+};
+use multi_key_map::MultiKeyMap;
+use ordered_hash_map::OrderedHashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+const PRESET_EXTENSIONS: [&str; 2] = ["nksf", "nksn"];
+
+pub struct FolderSource {
+    pub folders: Vec<PathBuf>,
+}
+
+impl LibrarySource for FolderSource {
+    fn get_name(&self) -> String {
+        self.folders
+            .iter()
+            .map(|f| f.to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn load(&self, progress: ProgressCallback) -> Result<Option<Library>, String> {
+        if self.folders.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(load(&self.folders, progress)))
+        }
+    }
+
+    fn get_stamp(&self) -> Option<SourceStamp> {
+        // files deep down the folders may change at any time, so only an empty source is known
+        if self.folders.is_empty() {
+            Some(SourceStamp::default())
+        } else {
+            None
+        }
+    }
+}
+
+// collects all preset files below the given folder, skipping hidden folders like .previews
+// and symlinked folders, linked preset files are picked up
+fn collect_files(folder: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return;
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        // file_type doesn't follow symlinks, so linked folders can't send us in circles
+        if file_type.is_dir() {
+            collect_files(&path, files);
+        } else if path.is_file()
+            && path
+                .extension()
+                .is_some_and(|e| PRESET_EXTENSIONS.iter().any(|x| e.eq_ignore_ascii_case(x)))
+        {
+            files.push(path);
+        }
+    }
+}
+
+// hands out ids for unique keys, starting at 1 since 0 means "none" for banks
+struct Ids<K> {
+    ids: HashMap<K, usize>,
+}
+
+impl<K: Eq + std::hash::Hash> Ids<K> {
+    fn new() -> Self {
+        Self {
+            ids: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: K) -> usize {
+        let next = self.ids.len() + 1;
+
+        *self.ids.entry(key).or_insert(next)
+    }
+}
+
+pub fn load(folders: &[PathBuf], progress: ProgressCallback) -> Library {
+    let mut products: HashMap<usize, Product> = HashMap::new();
+    let mut banks: HashMap<usize, Bank> = HashMap::new();
+    let mut categories: HashMap<usize, Category> = HashMap::new();
+    let mut modes: HashMap<usize, Mode> = HashMap::new();
+    let mut p: Vec<Preset> = vec![];
+    let mut warnings: Vec<LoadWarning> = vec![];
+
+    let mut product_ids: Ids<(String, String)> = Ids::new();
+    let mut bank_ids: Ids<(String, String, String)> = Ids::new();
+    let mut category_ids: Ids<(String, String, String)> = Ids::new();
+    let mut mode_ids: Ids<String> = Ids::new();
+
+    for folder in folders {
+        let mut files: Vec<PathBuf> = vec![];
+
+        collect_files(folder, &mut files);
+
+        let mut files_progress = Progress::new(progress, LoadingPhase::Files, files.len());
+
+        let folder_name = folder
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        for file_name in files {
+            files_progress.step();
+
+            // files without readable metadata still show up, named after the file
+            let info = match NksFileInfo::read(&file_name) {
+                Ok(info) => info,
+                Err(e) => {
+                    warnings.push(LoadWarning::UnreadableFile {
+                        path: file_name.clone(),
+                        error: e.to_string(),
+                    });
+                    NksFileInfo::default()
+                }
+            };
+
+            let name = if info.name.is_empty() {
+                file_name
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            } else {
+                info.name.clone()
+            };
+
+            let product_name = info
+                .bank_chain
+                .first()
+                .filter(|b| !b.is_empty())
+                .cloned()
+                .unwrap_or_else(|| folder_name.clone());
+
+            let product_id = product_ids.get((info.vendor.clone(), product_name.clone()));
+            let id = p.len() + 1;
+
+            products
+                .entry(product_id)
+                .or_insert_with(|| Product {
+                    id: product_id,
+                    name: product_name.clone(),
+                    content_dir: folder.to_string_lossy().into_owned(),
+                    vendor: info.vendor.as_str().into(),
+                    vendor_id: 0,
+                    upid: "".into(),
+                    presets: HashSet::new(),
+                    info: None,
+                    preview_dir: None,
+                })
+                .presets
+                .insert(id);
+
+            let bank = if info.bank_chain.is_empty() {
+                0
+            } else {
+                let entry = |i: usize| info.bank_chain.get(i).cloned().unwrap_or_default();
+                let bank_id = bank_ids.get((entry(0), entry(1), entry(2)));
+
+                banks
+                    .entry(bank_id)
+                    .or_insert_with(|| Bank {
+                        id: bank_id,
+                        entry1: entry(0),
+                        entry2: entry(1),
+                        entry3: entry(2),
+                        presets: HashSet::new(),
+                    })
+                    .presets
+                    .insert(id);
+
+                bank_id
+            };
+
+            let mut preset_categories: HashSet<usize> = HashSet::new();
+
+            for t in info.types.iter().filter(|t| !t.is_empty()) {
+                let entry = |i: usize| t.get(i).cloned().unwrap_or_default();
+                let category_id = category_ids.get((entry(0), entry(1), entry(2)));
+
+                categories
+                    .entry(category_id)
+                    .or_insert_with(|| Category {
+                        id: category_id,
+                        name: entry(0),
+                        subcategory: entry(1),
+                        subsubcategory: entry(2),
+                        presets: HashSet::new(),
+                    })
+                    .presets
+                    .insert(id);
+                preset_categories.insert(category_id);
+            }
+
+            let mut preset_modes: HashSet<usize> = HashSet::new();
+
+            for m in info.modes.iter() {
+                let mode_id = mode_ids.get(m.clone());
+
+                modes
+                    .entry(mode_id)
+                    .or_insert_with(|| Mode {
+                        id: mode_id,
+                        name: m.clone(),
+                        presets: HashSet::new(),
+                    })
+                    .presets
+                    .insert(id);
+                preset_modes.insert(mode_id);
+            }
+
+            p.push(Preset {
+                name,
+                vendor: info.vendor.as_str().into(),
+                vendor_id: 0,
+                comment: info.comment.clone(),
+                product_id: ProductKey::Id(product_id),
+                product_name: product_name.into(),
+                id,
+                file_name,
+                categories: preset_categories,
+                modes: preset_modes,
+                bank,
+                status: PresetStatus::Unknown,
+                source: 0,
+            });
+        }
+
+        files_progress.finish();
+    }
+
+    let mut library = Library {
+        banks: OrderedHashMap::new(),
+        categories: OrderedHashMap::new(),
+        modes: OrderedHashMap::new(),
+        products: MultiKeyMap::new(),
+        presets: OrderedHashMap::new(),
+        warnings,
+        snapshot_time: None,
+    };
+
+    let mut b: Vec<Bank> = banks.into_values().collect::<Vec<_>>();
+
+    b.sort();
+
+    b.into_iter().for_each(|b| {
+        library.banks.insert(b.id, b);
+    });
+
+    let mut c: Vec<Category> = categories.into_values().collect::<Vec<_>>();
+
+    c.sort();
+
+    c.into_iter().for_each(|c| {
+        library.categories.insert(c.id, c);
+    });
+
+    let mut m: Vec<Mode> = modes.into_values().collect::<Vec<_>>();
+
+    m.sort();
+
+    m.into_iter().for_each(|m| {
+        library.modes.insert(m.id, m);
+    });
+
+    products.into_values().for_each(|pr| {
+        library
+            .products
+            .insert_many(vec![ProductKey::Id(pr.id)], pr);
+    });
+
+    p.sort();
+
+    p.into_iter().for_each(|p| {
+        library.presets.insert(p.id, p);
+    });
+
+    library
+}
@@ -0,0 +1,309 @@
+use crate::product::Product;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+// file names NI uses for the pictures shown in its browsers, in order of preference
+const ARTWORK_FILES: [&str; 2] = ["VB_artwork.png", "MST_artwork.png"];
+const LOGO_FILES: [&str; 2] = ["MST_logo.png", "OSO_logo.png"];
+
+// the descriptor of the package holding the previews of products which don't ship their own
+const PREVIEW_LIBRARY: &str = "native browser preview library";
+
+// what the installed_products descriptor of a product tells about it
+#[derive(Clone, Serialize)]
+pub struct ProductInfo {
+    pub name: String,
+    pub version: Option<String>,
+    // in seconds since the epoch, only known if the descriptor tells
+    pub install_date: Option<u64>,
+    pub product_type: Option<String>,
+    pub artwork: Option<PathBuf>,
+    pub logo: Option<PathBuf>,
+    #[serde(skip)]
+    upid: String,
+    #[serde(skip)]
+    reg_key: String,
+    #[serde(skip)]
+    content_dir: String,
+}
+
+// the folder NI Access and Native Access write their product descriptors to
+pub fn get_default_location() -> PathBuf {
+    if cfg!(target_os = "macos") {
+        PathBuf::from("/Users/Shared/Native Instruments/installed_products")
+    } else {
+        PathBuf::from("C:/Users/Public/Documents/Native Instruments/installed_products")
+    }
+}
+
+// content paths are stored with either kind of slash and sometimes a trailing one
+fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/").trim_end_matches('/').to_lowercase()
+}
+
+fn string(json: &serde_json::Value, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|k| json[*k].as_str())
+        .map(|s| s.trim())
+        .find(|s| !s.is_empty())
+        .map(|s| s.to_string())
+}
+
+// timestamps beyond this many seconds (the year 5138) are taken for milliseconds
+const MAX_SECONDS: u64 = 100_000_000_000;
+
+// days since the epoch of a date in the proleptic gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// an ISO 8601 date like 2024-03-01, optionally followed by a time which is ignored
+fn parse_date(s: &str) -> Option<u64> {
+    let s = s.trim();
+
+    if !s.get(10..)?.is_empty() && !s[10..].starts_with(['T', ' ']) {
+        return None;
+    }
+
+    let mut parts = s[..10].split('-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+
+    if parts.next().is_some()
+        || !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+    {
+        return None;
+    }
+
+    u64::try_from(days_from_civil(year, month, day) * 86400).ok()
+}
+
+// descriptors written by newer installers carry the date either as a timestamp or as an ISO 8601 date
+fn date(json: &serde_json::Value, keys: &[&str]) -> Option<u64> {
+    keys.iter().find_map(|k| match &json[*k] {
+        serde_json::Value::Number(n) => n
+            .as_u64()
+            .map(|t| if t > MAX_SECONDS { t / 1000 } else { t }),
+        serde_json::Value::String(s) => parse_date(s),
+        _ => None,
+    })
+}
+
+fn find_file(folder: &Path, names: &[&str]) -> Option<PathBuf> {
+    names.iter().map(|n| folder.join(n)).find(|p| p.is_file())
+}
+
+impl ProductInfo {
+    fn load(path: &Path) -> Option<Self> {
+        let file = File::open(path).ok()?;
+        let json: serde_json::Value = serde_json::from_reader(file).ok()?;
+        let reg_key = path.file_stem()?.to_string_lossy().into_owned();
+        let name = string(&json, &["Name", "RegKey"]).unwrap_or_else(|| reg_key.clone());
+
+        // most descriptors carry no date, the time of the file isn't used instead
+        // as updates and backup restores rewrite it as well
+        let install_date = date(&json, &["InstallDate", "InstallationDate"]);
+
+        // pictures live next to installed_products, below NI Resources/image/<vendor>/<product>
+        let images = path
+            .parent()
+            .and_then(|p| p.parent())
+            .map(|p| p.join("NI Resources").join("image"));
+        let image_folder = images.and_then(|images| {
+            let product = name.to_lowercase();
+
+            fs::read_dir(images)
+                .ok()?
+                .filter_map(|e| e.ok())
+                .map(|e| e.path().join(&product))
+                .find(|p| p.is_dir())
+        });
+
+        Some(Self {
+            version: string(&json, &["ContentVersion", "Version"]),
+            install_date,
+            product_type: string(&json, &["ProductType", "Type"]),
+            artwork: image_folder
+                .as_ref()
+                .and_then(|f| find_file(f, &ARTWORK_FILES)),
+            logo: image_folder
+                .as_ref()
+                .and_then(|f| find_file(f, &LOGO_FILES)),
+            upid: string(&json, &["UPID"]).unwrap_or_default().to_lowercase(),
+            content_dir: string(&json, &["ContentDir"]).unwrap_or_default(),
+            reg_key: reg_key.to_lowercase(),
+            name,
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct InstalledProducts {
+    products: Vec<ProductInfo>,
+    by_upid: HashMap<String, usize>,
+    by_content_dir: HashMap<String, usize>,
+    by_name: HashMap<String, usize>,
+}
+
+impl InstalledProducts {
+    // reads every descriptor in the given folders, unreadable ones are left out
+    pub fn load(locations: &[PathBuf]) -> Self {
+        let mut installed = Self::default();
+
+        for location in locations {
+            let Ok(entries) = fs::read_dir(location) else {
+                continue;
+            };
+
+            for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+                if !path
+                    .extension()
+                    .is_some_and(|e| e.eq_ignore_ascii_case("json"))
+                {
+                    continue;
+                }
+
+                if let Some(info) = ProductInfo::load(&path) {
+                    installed.add(info);
+                }
+            }
+        }
+
+        installed
+    }
+
+    fn add(&mut self, info: ProductInfo) {
+        let index = self.products.len();
+
+        // the first location wins if a product is described twice
+        if !info.upid.is_empty() {
+            self.by_upid.entry(info.upid.clone()).or_insert(index);
+        }
+
+        if !info.content_dir.is_empty() {
+            self.by_content_dir
+                .entry(normalize_path(&info.content_dir))
+                .or_insert(index);
+        }
+
+        self.by_name.entry(info.reg_key.clone()).or_insert(index);
+        self.by_name
+            .entry(info.name.to_lowercase())
+            .or_insert(index);
+        self.products.push(info);
+    }
+
+    pub fn find(&self, product: &Product) -> Option<&ProductInfo> {
+        let upid = product.upid.to_lowercase();
+        let content_dir = normalize_path(&product.content_dir);
+
+        (!upid.is_empty())
+            .then(|| self.by_upid.get(&upid))
+            .flatten()
+            .or_else(|| {
+                (!content_dir.is_empty())
+                    .then(|| self.by_content_dir.get(&content_dir))
+                    .flatten()
+            })
+            .or_else(|| self.by_name.get(&product.name.to_lowercase()))
+            .map(|i| &self.products[*i])
+    }
+
+    // the content folder of the preview library, if it is installed
+    pub fn get_preview_library(&self) -> Option<PathBuf> {
+        self.by_name
+            .get(PREVIEW_LIBRARY)
+            .map(|i| &self.products[*i])
+            .filter(|p| p.reg_key == PREVIEW_LIBRARY && !p.content_dir.is_empty())
+            .map(|p| PathBuf::from(&p.content_dir))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn counts_days_since_the_epoch() {
+        for (date, days) in [
+            ((1970, 1, 1), 0),
+            ((1969, 12, 31), -1),
+            ((1970, 3, 1), 59),
+            ((2000, 2, 29), 11016),
+            ((2000, 3, 1), 11017),
+            ((2024, 1, 1), 19723),
+            ((2024, 2, 29), 19782),
+            ((2100, 3, 1), 47541),
+        ] {
+            assert_eq!(days_from_civil(date.0, date.1, date.2), days, "{:?}", date);
+        }
+    }
+
+    #[test]
+    fn parses_dates() {
+        for (text, seconds) in [
+            ("1970-01-01", Some(0)),
+            ("2024-02-29", Some(1709164800)),
+            ("2024-02-29T13:45:00Z", Some(1709164800)),
+            ("2024-02-29 13:45", Some(1709164800)),
+            (" 2024-03-01 ", Some(1709251200)),
+            ("2023-02-29", None),
+            ("2024-04-31", None),
+            ("2024-13-01", None),
+            ("2024-00-10", None),
+            ("2024-01-00", None),
+            ("2024-1-5", None),
+            ("2024-01-015", None),
+            ("1969-12-31", None),
+            ("20240101", None),
+            ("", None),
+            ("2024-01-01-", None),
+        ] {
+            assert_eq!(parse_date(text), seconds, "{}", text);
+        }
+    }
+
+    #[test]
+    fn reads_timestamps() {
+        let keys = ["InstallDate"];
+
+        assert_eq!(
+            date(&json!({"InstallDate": 1709164800}), &keys),
+            Some(1709164800)
+        );
+        assert_eq!(
+            date(&json!({"InstallDate": 1709164800123u64}), &keys),
+            Some(1709164800)
+        );
+        assert_eq!(date(&json!({"InstallDate": -1}), &keys), None);
+        assert_eq!(date(&json!({"InstallDate": true}), &keys), None);
+        assert_eq!(date(&json!({}), &keys), None);
+        assert_eq!(
+            date(
+                &json!({"InstallationDate": "2024-03-01"}),
+                &["InstallDate", "InstallationDate"]
+            ),
+            Some(1709251200)
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use std::{collections::HashSet, sync::Arc};
+
+// hands out one shared copy per distinct string
+// tens of thousands of presets only ever name a few hundred vendors and products
+#[derive(Default)]
+pub struct Interner {
+    strings: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    pub fn intern(&mut self, s: &str) -> Arc<str> {
+        if let Some(interned) = self.strings.get(s) {
+            return interned.clone();
+        }
+
+        let interned: Arc<str> = Arc::from(s);
+
+        self.strings.insert(interned.clone());
+        interned
+    }
+}
@@ -1,240 +1,466 @@
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
+mod cache;
 mod category;
+mod db3;
+mod diff;
+mod duplicates;
+mod features;
+mod fnv;
+mod folder;
+mod installed_products;
+mod intern;
+mod library;
+mod load_report;
+mod loudness;
+mod msgpack;
+mod nks;
 mod paginated_result;
 mod preset;
 mod product;
-
+mod progress;
+mod protocol;
+mod query;
+mod report;
+mod schema;
+mod settings;
+mod snapshot;
+mod source;
+mod tree;
+mod vendor;
+mod waveform;
+
+use cache::LibraryCache;
 use category::{Bank, Category, Mode};
+use db3::Db3Source;
+use diff::LibraryDiff;
 use directories::BaseDirs;
+use duplicates::DuplicateGroup;
+use features::FeatureStore;
+use folder::FolderSource;
+use installed_products::InstalledProducts;
+use library::Library;
+use load_report::LoadReport;
+use loudness::{Loudness, NormalizationSettings};
 use multi_key_map::MultiKeyMap;
+use nks::{NksError, NksFileInfo, ParameterPage};
 use ordered_hash_map::OrderedHashMap;
 use paginated_result::PaginatedResult;
 use preset::Preset;
-use product::{Product, ProductKey};
-use rodio::{Decoder, OutputStreamBuilder, Sink};
-use rusqlite::{Connection, OpenFlags};
+use product::{Product, ProductDetails, ProductKey};
+use progress::LoadingProgress;
+use protocol::Resource;
+use query::{PresetQuery, SearchIndex, paginate};
+use report::LibraryReport;
+use rodio::{Decoder, OutputStreamBuilder, Sink, Source};
+use settings::Settings;
+use snapshot::Snapshot;
+use source::{LibrarySource, SourceInfo};
 use std::{
-    collections::{HashMap, HashSet},
-    fs::File,
-    path::PathBuf,
-    sync::Mutex,
+    collections::HashMap,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::SystemTime,
 };
 use tauri::{
-    Manager, State,
+    AppHandle, Emitter, Manager, State,
     async_runtime::{Sender, channel, spawn_blocking},
+    http::{Request, Response},
 };
+use tree::TreeNode;
+use vendor::{Vendor, VendorAliases};
+use waveform::Waveform;
 
+// every part has its own lock, so a slow query never holds up playback or the loader
 struct AppState {
-    banks: OrderedHashMap<usize, Bank>,
-    categories: OrderedHashMap<usize, Category>,
+    analyzing: AtomicBool,
+    features: Mutex<FeatureStore>,
+    // bumped whenever a load is requested, only the latest one may publish its library
+    generation: AtomicU64,
+    library: Snapshot<LibraryState>,
+    loading: Mutex<LoadingState>,
+    playback: PlaybackState,
+    settings: Mutex<Settings>,
+}
+
+// the loaded library, never changed in place but replaced as a whole
+// the parts are shared, so a new version only differing in some of them is cheap to build
+#[derive(Clone)]
+struct LibraryState {
+    banks: Arc<OrderedHashMap<usize, Bank>>,
+    categories: Arc<OrderedHashMap<usize, Category>>,
     db_found: bool,
+    load_reports: Arc<Vec<LoadReport>>,
+    modes: Arc<OrderedHashMap<usize, Mode>>,
+    products: Arc<MultiKeyMap<ProductKey, Product>>,
+    presets: Arc<OrderedHashMap<usize, Preset>>,
+    // names and comments don't change when presets get validated, so it is shared as well
+    search_index: Arc<SearchIndex>,
+    sources: Arc<Vec<SourceInfo>>,
+    vendors: Arc<OrderedHashMap<usize, Vendor>>,
+}
+
+impl LibraryState {
+    fn new(
+        library: Library,
+        vendors: OrderedHashMap<usize, Vendor>,
+        infos: Vec<SourceInfo>,
+        reports: Vec<LoadReport>,
+    ) -> Self {
+        Self {
+            banks: Arc::new(library.banks),
+            categories: Arc::new(library.categories),
+            db_found: get_db3_path().exists(),
+            load_reports: Arc::new(reports),
+            modes: Arc::new(library.modes),
+            products: Arc::new(library.products),
+            search_index: Arc::new(SearchIndex::new(library.presets.values())),
+            presets: Arc::new(library.presets),
+            sources: Arc::new(infos),
+            vendors: Arc::new(vendors),
+        }
+    }
+}
+
+#[derive(Default)]
+struct LoadingState {
     loading: bool,
-    modes: OrderedHashMap<usize, Mode>,
-    products: MultiKeyMap<ProductKey, Product>,
-    presets: OrderedHashMap<usize, Preset>,
-    preview_sender: Sender<PathBuf>,
-    vendors: Vec<String>,
+    progress: Option<LoadingProgress>,
+    // the library shown comes from the cache and is reloaded from the sources in the background
+    refreshing: bool,
+}
+
+struct PlaybackState {
+    // measured loudness per preview file, so every file only gets analyzed once
+    // the gain is derived from it on every request, so changing the target needs no new measurement
+    loudness: Mutex<HashMap<FileKey, Option<Loudness>>>,
+    sender: Sender<PreviewRequest>,
+}
+
+// a file in a given state, an edited file gets a new key
+#[derive(PartialEq, Eq, Hash)]
+struct FileKey {
+    path: PathBuf,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileKey {
+    fn new(path: &Path) -> Self {
+        let metadata = fs::metadata(path).ok();
+
+        Self {
+            path: path.to_path_buf(),
+            size: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+            modified: metadata.and_then(|m| m.modified().ok()),
+        }
+    }
+}
+
+struct PreviewRequest {
+    path: PathBuf,
+    // linear gain applied while playing
+    gain: f32,
 }
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 
 #[tauri::command]
 async fn get_categories(
-    state: State<'_, Mutex<AppState>>,
-    vendors: Vec<String>,
+    state: State<'_, AppState>,
+    vendors: Vec<usize>,
     products: Vec<usize>,
     modes: Vec<usize>,
     banks: Vec<usize>,
+    sources: Vec<usize>,
 ) -> Result<Vec<Category>, ()> {
     let products = products.into_iter().map(ProductKey::Id).collect::<Vec<_>>();
-    let state = state.lock().unwrap();
+    let library = state.library.load();
+    let query = PresetQuery {
+        vendors: &vendors,
+        products: &products,
+        modes: &modes,
+        banks: &banks,
+        sources: &sources,
+        include_broken: true,
+        ..Default::default()
+    };
 
-    Ok(state
+    Ok(library
         .categories
         .values()
-        .filter(|c| {
-            (vendors.is_empty()
-                || c.presets
-                    .iter()
-                    .any(|p| vendors.contains(&state.presets.get(p).unwrap().vendor)))
-                && (products.is_empty()
-                    || c.presets
-                        .iter()
-                        .any(|p| products.contains(&state.presets.get(p).unwrap().product_id)))
-                && (modes.is_empty()
-                    || c.presets.iter().any(|p| {
-                        modes
-                            .iter()
-                            .any(|m| state.presets.get(p).unwrap().modes.contains(m))
-                    }))
-                && (banks.is_empty()
-                    || c.presets
-                        .iter()
-                        .any(|p| banks.contains(&state.presets.get(p).unwrap().bank)))
-        })
+        .filter(|c| query.matches_any(&library.presets, &c.presets))
         .cloned()
         .collect::<Vec<_>>())
 }
 
+#[tauri::command]
+async fn get_category_tree(
+    state: State<'_, AppState>,
+    vendors: Vec<usize>,
+    products: Vec<usize>,
+    modes: Vec<usize>,
+    banks: Vec<usize>,
+    sources: Vec<usize>,
+    include_broken: bool,
+) -> Result<Vec<TreeNode>, ()> {
+    let products = products.into_iter().map(ProductKey::Id).collect::<Vec<_>>();
+    let library = state.library.load();
+    let query = PresetQuery {
+        vendors: &vendors,
+        products: &products,
+        modes: &modes,
+        banks: &banks,
+        sources: &sources,
+        include_broken,
+        ..Default::default()
+    };
+
+    Ok(tree::build(
+        library.categories.values().map(|c| {
+            (
+                vec![
+                    c.name.clone(),
+                    c.subcategory.clone(),
+                    c.subsubcategory.clone(),
+                ],
+                c.id,
+                &c.presets,
+            )
+        }),
+        |p| query.matches(library.presets.get(&p).unwrap()),
+    ))
+}
+
+// bank chains grouped by the product their presets belong to
+// selecting a node filters by bank ids only, so every bank has to show up exactly once
+// banks holding presets of several products, like the (No bank) pseudo entry, are listed on their own
+fn build_bank_tree(
+    library: &LibraryState,
+    vendors: &[usize],
+    products: &[ProductKey],
+    categories: &[usize],
+    modes: &[usize],
+    sources: &[usize],
+    include_broken: bool,
+) -> Vec<TreeNode> {
+    let mut entries: Vec<(Option<&Product>, &Bank)> = library
+        .banks
+        .values()
+        .map(|bank| {
+            let mut products = bank
+                .presets
+                .iter()
+                .filter_map(|p| library.products.get(&library.presets.get(p)?.product_id));
+            let product = products.next();
+            let shared = product.is_some_and(|first| products.any(|p| p.id != first.id));
+
+            (product.filter(|_| !shared), bank)
+        })
+        .collect::<Vec<_>>();
+
+    // banks are sorted already, the sort is stable
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let query = PresetQuery {
+        vendors,
+        products,
+        categories,
+        modes,
+        sources,
+        include_broken,
+        ..Default::default()
+    };
+
+    tree::build(
+        entries.iter().map(|(product, bank)| {
+            (
+                vec![
+                    product.map(|p| p.name.clone()).unwrap_or_default(),
+                    bank.entry1.clone(),
+                    bank.entry2.clone(),
+                    bank.entry3.clone(),
+                ],
+                bank.id,
+                &bank.presets,
+            )
+        }),
+        |p| query.matches(library.presets.get(&p).unwrap()),
+    )
+}
+
+#[tauri::command]
+async fn get_bank_tree(
+    state: State<'_, AppState>,
+    vendors: Vec<usize>,
+    products: Vec<usize>,
+    categories: Vec<usize>,
+    modes: Vec<usize>,
+    sources: Vec<usize>,
+    include_broken: bool,
+) -> Result<Vec<TreeNode>, ()> {
+    let products = products.into_iter().map(ProductKey::Id).collect::<Vec<_>>();
+    let library = state.library.load();
+
+    Ok(build_bank_tree(
+        &library,
+        &vendors,
+        &products,
+        &categories,
+        &modes,
+        &sources,
+        include_broken,
+    ))
+}
+
+// column browser, returns the entries one level below the given product / bank path
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn get_bank_children(
+    state: State<'_, AppState>,
+    path: Vec<String>,
+    vendors: Vec<usize>,
+    products: Vec<usize>,
+    categories: Vec<usize>,
+    modes: Vec<usize>,
+    sources: Vec<usize>,
+    include_broken: bool,
+) -> Result<Vec<TreeNode>, ()> {
+    let products = products.into_iter().map(ProductKey::Id).collect::<Vec<_>>();
+    let library = state.library.load();
+
+    Ok(tree::get_children(
+        build_bank_tree(
+            &library,
+            &vendors,
+            &products,
+            &categories,
+            &modes,
+            &sources,
+            include_broken,
+        ),
+        &path,
+    ))
+}
+
 #[tauri::command]
 async fn get_banks(
-    state: State<'_, Mutex<AppState>>,
-    vendors: Vec<String>,
+    state: State<'_, AppState>,
+    vendors: Vec<usize>,
     products: Vec<usize>,
     modes: Vec<usize>,
     categories: Vec<usize>,
+    sources: Vec<usize>,
 ) -> Result<Vec<Bank>, ()> {
     let products = products.into_iter().map(ProductKey::Id).collect::<Vec<_>>();
-    let state = state.lock().unwrap();
+    let library = state.library.load();
+    let query = PresetQuery {
+        vendors: &vendors,
+        products: &products,
+        modes: &modes,
+        categories: &categories,
+        sources: &sources,
+        include_broken: true,
+        ..Default::default()
+    };
 
-    Ok(state
+    Ok(library
         .banks
         .values()
-        .filter(|b| {
-            (vendors.is_empty()
-                || b.presets
-                    .iter()
-                    .any(|p| vendors.contains(&state.presets.get(p).unwrap().vendor)))
-                && (products.is_empty()
-                    || b.presets
-                        .iter()
-                        .any(|p| products.contains(&state.presets.get(p).unwrap().product_id)))
-                && (modes.is_empty()
-                    || b.presets.iter().any(|p| {
-                        modes
-                            .iter()
-                            .any(|m| state.presets.get(p).unwrap().modes.contains(m))
-                    }))
-                && (categories.is_empty()
-                    || b.presets.iter().any(|p| {
-                        categories
-                            .iter()
-                            .any(|c| state.presets.get(p).unwrap().categories.contains(c))
-                    }))
-        })
+        .filter(|b| query.matches_any(&library.presets, &b.presets))
         .cloned()
         .collect::<Vec<_>>())
 }
 
 #[tauri::command]
 async fn get_modes(
-    state: State<'_, Mutex<AppState>>,
-    vendors: Vec<String>,
+    state: State<'_, AppState>,
+    vendors: Vec<usize>,
     products: Vec<usize>,
     categories: Vec<usize>,
     banks: Vec<usize>,
+    sources: Vec<usize>,
 ) -> Result<Vec<Mode>, ()> {
     let products = products.into_iter().map(ProductKey::Id).collect::<Vec<_>>();
-    let state = state.lock().unwrap();
+    let library = state.library.load();
+    let query = PresetQuery {
+        vendors: &vendors,
+        products: &products,
+        categories: &categories,
+        banks: &banks,
+        sources: &sources,
+        include_broken: true,
+        ..Default::default()
+    };
 
-    Ok(state
+    Ok(library
         .modes
         .values()
-        .filter(|m| {
-            (vendors.is_empty()
-                || m.presets
-                    .iter()
-                    .any(|p| vendors.contains(&state.presets.get(p).unwrap().vendor)))
-                && (products.is_empty()
-                    || m.presets
-                        .iter()
-                        .any(|p| products.contains(&state.presets.get(p).unwrap().product_id)))
-                && (categories.is_empty()
-                    || m.presets.iter().any(|p| {
-                        categories
-                            .iter()
-                            .any(|c| state.presets.get(p).unwrap().categories.contains(c))
-                    }))
-                && (banks.is_empty()
-                    || m.presets
-                        .iter()
-                        .any(|p| banks.contains(&state.presets.get(p).unwrap().bank)))
-        })
+        .filter(|m| query.matches_any(&library.presets, &m.presets))
         .cloned()
         .collect::<Vec<_>>())
 }
 
 #[tauri::command]
 async fn get_presets(
-    state: State<'_, Mutex<AppState>>,
-    vendors: Vec<String>,
+    state: State<'_, AppState>,
+    vendors: Vec<usize>,
     products: Vec<usize>,
     categories: Vec<usize>,
     modes: Vec<usize>,
     banks: Vec<usize>,
-    mut query: String,
+    sources: Vec<usize>,
+    include_broken: bool,
+    query: String,
     offset: usize,
     limit: usize,
 ) -> Result<PaginatedResult<Preset>, ()> {
-    query = query.to_lowercase();
-    let products: Vec<ProductKey> = products.into_iter().map(ProductKey::Id).collect::<Vec<_>>();
-    let state = state.lock().unwrap();
-
-    let presets: Vec<Preset> = state
-        .presets
-        .values()
-        .filter(|p| {
-            (vendors.is_empty() || vendors.contains(&p.vendor))
-                && (products.is_empty() || products.contains(&p.product_id))
-                && (categories.is_empty() || categories.iter().any(|c| p.categories.contains(c)))
-                && (modes.is_empty() || modes.iter().any(|m| p.modes.contains(m)))
-                && (banks.is_empty() || banks.contains(&p.bank))
-                && (query.is_empty()
-                    || p.name.to_lowercase().contains(&query)
-                    || p.comment.to_lowercase().contains(&query))
-        })
-        .cloned()
-        .collect::<Vec<_>>();
-
-    let results = presets
-        .iter()
-        .skip(offset)
-        .take(limit)
-        .cloned()
-        .collect::<Vec<_>>();
-
-    let start = offset + 1;
-    let end = offset + results.len();
+    let library = state.library.load();
+    let products = products.into_iter().map(ProductKey::Id).collect::<Vec<_>>();
+    let ids = PresetQuery {
+        vendors: &vendors,
+        products: &products,
+        categories: &categories,
+        modes: &modes,
+        banks: &banks,
+        sources: &sources,
+        include_broken,
+        text: &query.to_lowercase(),
+    }
+    .filter(&library.presets, &library.search_index);
 
-    Ok(PaginatedResult {
-        results,
-        start,
-        end,
-        total: presets.len(),
-    })
+    Ok(paginate(&library.presets, &ids, offset, limit))
 }
 
 #[tauri::command]
 async fn get_products(
-    state: State<'_, Mutex<AppState>>,
-    vendors: Vec<String>,
+    state: State<'_, AppState>,
+    vendors: Vec<usize>,
     categories: Vec<usize>,
     modes: Vec<usize>,
     banks: Vec<usize>,
+    sources: Vec<usize>,
 ) -> Result<Vec<Product>, ()> {
-    let state = state.lock().unwrap();
+    let library = state.library.load();
+    let query = PresetQuery {
+        vendors: &vendors,
+        categories: &categories,
+        modes: &modes,
+        banks: &banks,
+        sources: &sources,
+        include_broken: true,
+        ..Default::default()
+    };
 
-    let mut p: Vec<Product> = state
+    let mut p: Vec<Product> = library
         .products
         .values()
-        .filter(|p| {
-            (vendors.is_empty() || vendors.contains(&p.vendor))
-                && (categories.is_empty()
-                    || categories.iter().any(|c| {
-                        p.presets
-                            .iter()
-                            .any(|pr| state.presets.get(pr).unwrap().categories.contains(c))
-                    }))
-                && (modes.is_empty()
-                    || modes.iter().any(|m| {
-                        p.presets
-                            .iter()
-                            .any(|pr| state.presets.get(pr).unwrap().modes.contains(m))
-                    }))
-                && (banks.is_empty()
-                    || p.presets
-                        .iter()
-                        .any(|pr| banks.contains(&state.presets.get(pr).unwrap().bank)))
-        })
+        .filter(|p| query.matches_any(&library.presets, &p.presets))
         .cloned()
         .collect::<Vec<_>>();
 
@@ -245,140 +471,458 @@ async fn get_products(
 
 #[tauri::command]
 async fn get_vendors(
-    state: State<'_, Mutex<AppState>>,
+    state: State<'_, AppState>,
     products: Vec<usize>,
     categories: Vec<usize>,
     modes: Vec<usize>,
     banks: Vec<usize>,
-) -> Result<Vec<String>, ()> {
-    let state = state.lock().unwrap();
-    Ok(state
+    sources: Vec<usize>,
+) -> Result<Vec<Vendor>, ()> {
+    let products = products.into_iter().map(ProductKey::Id).collect::<Vec<_>>();
+    let library = state.library.load();
+    let query = PresetQuery {
+        products: &products,
+        categories: &categories,
+        modes: &modes,
+        banks: &banks,
+        sources: &sources,
+        include_broken: true,
+        ..Default::default()
+    };
+
+    Ok(library
         .vendors
-        .iter()
-        .filter(|v| {
-            (products.is_empty()
-                || products
-                    .iter()
-                    .any(|p| &state.products.get(&ProductKey::Id(*p)).unwrap().vendor == *v))
-                && (categories.is_empty()
-                    || categories.iter().any(|c| {
-                        state
-                            .categories
-                            .get(c)
-                            .unwrap()
-                            .presets
-                            .iter()
-                            .any(|p| &state.presets.get(p).unwrap().vendor == *v)
-                    }))
-                && (modes.is_empty()
-                    || modes.iter().any(|m| {
-                        state
-                            .modes
-                            .get(m)
-                            .unwrap()
-                            .presets
-                            .iter()
-                            .any(|p| &state.presets.get(p).unwrap().vendor == *v)
-                    }))
-                && (banks.is_empty()
-                    || banks.iter().any(|b| {
-                        state
-                            .banks
-                            .get(b)
-                            .unwrap()
-                            .presets
-                            .iter()
-                            .any(|pr| &state.presets.get(pr).unwrap().vendor == *v)
-                    }))
-        })
+        .values()
+        .filter(|v| query.matches_any(&library.presets, &v.presets))
         .cloned()
         .collect::<Vec<_>>())
 }
 
 #[tauri::command]
-fn play_preset(state: State<'_, Mutex<AppState>>, preset: usize) {
-    let state = state.lock().unwrap();
-    let preset = state.presets.get(&preset).unwrap();
+fn get_product_details(state: State<'_, AppState>, product: usize) -> Option<ProductDetails> {
+    let library = state.library.load();
+    let product = library.products.get(&ProductKey::Id(product))?;
+
+    Some(ProductDetails {
+        content_dir: product.content_dir.clone(),
+        upid: product.upid.clone(),
+        presets: product.presets.len(),
+        product: product.clone(),
+    })
+}
 
-    let preview_path: Option<PathBuf> = if preset
-        .file_name
-        .extension()
-        .unwrap()
-        .eq_ignore_ascii_case("wav")
-        && preset.file_name.exists()
-    {
-        Some(preset.file_name.clone())
+// the frontend may still hold ids of a library which has been replaced by a reload since
+const UNKNOWN_PRESET: &str = "unknown preset";
+
+fn get_preview_path(library: &LibraryState, preset: &Preset) -> Option<PathBuf> {
+    preset.get_preview_path(library.products.get(&preset.product_id)?)
+}
+
+#[tauri::command]
+async fn play_preset(state: State<'_, AppState>, preset: usize) -> Result<(), String> {
+    let preview_path = {
+        let library = state.library.load();
+        let preset = library.presets.get(&preset).ok_or(UNKNOWN_PRESET)?;
+
+        get_preview_path(&library, preset)
+    };
+
+    let Some(preview_path) = preview_path else {
+        return Ok(());
+    };
+
+    let normalization = state.settings.lock().unwrap().normalization;
+    let gain = if normalization.enabled {
+        get_preview_gain(&state, &preview_path, normalization.target).await
     } else {
-        let p = preset
-            .file_name
-            .parent()
-            .unwrap()
-            .join(".previews")
-            .join(format!(
-                "{}.ogg",
-                preset.file_name.file_name().unwrap().to_str().unwrap()
-            ));
-        if p.exists() {
-            Some(p)
-        } else {
-            // we need to search for the previews library by name instruments
-            let json_path: PathBuf = if cfg!(target_os = "macos") {
-                PathBuf::from(
-                    "/Users/Shared/Native Instruments/installed_products/Native Browser Preview Library.json",
-                )
-            } else {
-                PathBuf::from(
-                    "C:/Users/Public/Documents/Native Instruments/installed_products/Native Browser Preview Library.json",
-                )
-            };
-
-            if json_path.exists() {
-                let file = File::open(json_path).unwrap();
-
-                let json: serde_json::Value = serde_json::from_reader(&file).unwrap();
-
-                let preview_content_dir = PathBuf::from(json["ContentDir"].as_str().unwrap());
-
-                let product = state.products.get(&preset.product_id).unwrap();
-
-                if !product.upid.is_empty() {
-                    Some(
-                        preview_content_dir
-                            .join("Samples")
-                            .join(&product.upid)
-                            .join(preset.file_name.strip_prefix(&product.content_dir).unwrap())
-                            .parent()
-                            .unwrap()
-                            .join(".previews")
-                            .join(format!(
-                                "{}.ogg",
-                                preset.file_name.file_name().unwrap().to_str().unwrap()
-                            )),
-                    )
-                } else {
-                    None
+        1.0
+    };
+
+    // awaiting a full channel only suspends this command, never the main thread
+    state
+        .playback
+        .sender
+        .send(PreviewRequest {
+            path: preview_path,
+            gain,
+        })
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// measures off the audio thread, so whatever is playing keeps playing meanwhile
+async fn get_preview_gain(state: &AppState, path: &Path, target: f32) -> f32 {
+    let key = FileKey::new(path);
+    let cached = state.playback.loudness.lock().unwrap().get(&key).copied();
+
+    let loudness = match cached {
+        Some(loudness) => loudness,
+        None => {
+            let path = path.to_path_buf();
+            let loudness = spawn_blocking(move || {
+                File::open(&path)
+                    .ok()
+                    .and_then(|f| Decoder::try_from(f).ok())
+                    .and_then(Loudness::measure)
+            })
+            .await
+            .unwrap();
+
+            state
+                .playback
+                .loudness
+                .lock()
+                .unwrap()
+                .insert(key, loudness);
+            loudness
+        }
+    };
+
+    loudness.map(|l| l.gain(target)).unwrap_or(1.0)
+}
+
+#[tauri::command]
+async fn get_preset_waveform(
+    state: State<'_, AppState>,
+    preset: usize,
+    resolution: usize,
+) -> Result<Option<Waveform>, ()> {
+    let preview_path = {
+        let library = state.library.load();
+
+        library
+            .presets
+            .get(&preset)
+            .and_then(|p| get_preview_path(&library, p))
+    };
+
+    match preview_path {
+        Some(preview_path) => {
+            Ok(
+                spawn_blocking(move || Waveform::load(&preview_path, resolution, &get_cache_dir()))
+                    .await
+                    .unwrap(),
+            )
+        }
+        None => Ok(None),
+    }
+}
+
+// checks every preset's file and content dir, runs after loading without blocking the ui
+fn validate_presets(state: &AppState) {
+    let library = state.library.load();
+    let mut presets: OrderedHashMap<usize, Preset> = OrderedHashMap::new();
+
+    for p in library.presets.values() {
+        let status = p.check_status(library.products.get(&p.product_id).unwrap());
+
+        presets.insert(
+            p.id,
+            Preset {
+                status,
+                ..p.clone()
+            },
+        );
+    }
+
+    // a reload which finished in the meantime wins, its presets get checked on their own
+    state.library.replace(
+        &library,
+        LibraryState {
+            presets: Arc::new(presets),
+            ..(*library).clone()
+        },
+    );
+}
+
+#[tauri::command]
+fn analyze_previews(state: State<'_, AppState>, app: AppHandle) {
+    if state.loading.lock().unwrap().loading || state.analyzing.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let cache_dir = get_cache_dir();
+        let library = state.library.load();
+
+        let pending: Vec<(PathBuf, PathBuf)> = library
+            .presets
+            .values()
+            .filter_map(|p| {
+                get_preview_path(&library, p)
+                    .map(|preview_path| (p.file_name.clone(), preview_path))
+            })
+            .collect::<Vec<_>>();
+
+        let mut analyzed = 0;
+
+        for (file_name, preview_path) in pending {
+            if state
+                .features
+                .lock()
+                .unwrap()
+                .is_current(&file_name, &preview_path)
+            {
+                continue;
+            }
+
+            if let Some(features) = FeatureStore::analyze(&preview_path) {
+                let mut store = state.features.lock().unwrap();
+
+                store.insert(file_name, &preview_path, features);
+                analyzed += 1;
+
+                // persist now and then, so an interrupted pass doesn't lose everything
+                if analyzed % 100 == 0 {
+                    store.save(&cache_dir);
                 }
-            } else {
-                None
             }
         }
+
+        state.features.lock().unwrap().save(&cache_dir);
+        state.analyzing.store(false, Ordering::SeqCst);
+    });
+}
+
+#[tauri::command]
+async fn get_similar_presets(
+    state: State<'_, AppState>,
+    preset: usize,
+    limit: usize,
+) -> Result<Vec<Preset>, ()> {
+    let library = state.library.load();
+    let Some(preset) = library.presets.get(&preset) else {
+        return Ok(vec![]);
+    };
+    let (file_name, preview_path) = (preset.file_name.clone(), get_preview_path(&library, preset));
+
+    let Some(preview_path) = preview_path else {
+        return Ok(vec![]);
     };
 
-    if let Some(preview_path) = preview_path {
-        if preview_path.exists() {
-            state.preview_sender.blocking_send(preview_path).unwrap();
+    if !state
+        .features
+        .lock()
+        .unwrap()
+        .is_current(&file_name, &preview_path)
+    {
+        let path = preview_path.clone();
+
+        match spawn_blocking(move || FeatureStore::analyze(&path))
+            .await
+            .unwrap()
+        {
+            Some(features) => {
+                state
+                    .features
+                    .lock()
+                    .unwrap()
+                    .insert(file_name.clone(), &preview_path, features)
+            }
+            None => return Ok(vec![]),
         }
     }
+
+    let store = state.features.lock().unwrap();
+    let features = store.get(&file_name).unwrap();
+
+    let mut similar: Vec<(f32, &Preset)> = library
+        .presets
+        .values()
+        .filter(|p| p.file_name != file_name)
+        .filter_map(|p| store.get(&p.file_name).map(|f| (features.distance(f), p)))
+        .collect::<Vec<_>>();
+
+    similar.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+    Ok(similar
+        .into_iter()
+        .take(limit)
+        .map(|(_, p)| p.clone())
+        .collect::<Vec<_>>())
 }
 
 #[tauri::command]
-fn db_found(state: State<'_, Mutex<AppState>>) -> bool {
-    state.lock().unwrap().db_found
+async fn get_related_presets(
+    state: State<'_, AppState>,
+    preset: usize,
+    offset: usize,
+    limit: usize,
+) -> Result<PaginatedResult<Preset>, String> {
+    let library = state.library.load();
+    let preset = library.presets.get(&preset).ok_or(UNKNOWN_PRESET)?;
+    let tokens = preset.get_name_tokens();
+
+    let mut related: Vec<(f32, &Preset)> = library
+        .presets
+        .values()
+        .filter(|p| p.id != preset.id)
+        .map(|p| (preset.similarity(&tokens, p), p))
+        .filter(|(score, _)| *score > 0.0)
+        .collect::<Vec<_>>();
+
+    related.sort_by(|(a, pa), (b, pb)| b.total_cmp(a).then_with(|| pa.cmp(pb)));
+
+    let results = related
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .map(|(_, p)| (*p).clone())
+        .collect::<Vec<_>>();
+
+    let start = offset + 1;
+    let end = offset + results.len();
+
+    Ok(PaginatedResult {
+        results,
+        start,
+        end,
+        total: related.len(),
+    })
+}
+
+#[tauri::command]
+async fn get_duplicate_presets(state: State<'_, AppState>) -> Result<Vec<DuplicateGroup>, ()> {
+    let library = state.library.load();
+
+    Ok(spawn_blocking(move || {
+        duplicates::find_duplicates(library.presets.values(), &library.products)
+    })
+    .await
+    .unwrap())
+}
+
+async fn build_library_report(state: &AppState) -> LibraryReport {
+    let library = state.library.load();
+    let mut report = LibraryReport::new(
+        library.presets.values(),
+        library.products.values(),
+        library.categories.values(),
+        library.modes.values(),
+        library.banks.values(),
+    );
+
+    spawn_blocking(move || {
+        report.check_files(library.presets.values(), &library.products);
+        report
+    })
+    .await
+    .unwrap()
+}
+
+#[tauri::command]
+async fn get_library_report(state: State<'_, AppState>) -> Result<LibraryReport, ()> {
+    Ok(build_library_report(&state).await)
+}
+
+#[tauri::command]
+async fn export_library_report(state: State<'_, AppState>, path: String) -> Result<(), String> {
+    let report = build_library_report(&state).await;
+    let file = File::create(path).map_err(|e| e.to_string())?;
+
+    serde_json::to_writer_pretty(file, &report).map_err(|e| e.to_string())
+}
+
+fn diff_databases(old: PathBuf, new: PathBuf) -> Result<LibraryDiff, String> {
+    // Komplete Kontrol may be writing to either of them, so both get copied first
+    let load = |path: &PathBuf| {
+        let (conn, _) = db3::snapshot(path).map_err(|e| e.to_string())?;
+
+        db3::load(&conn, &|_, _, _| {}).map_err(|e| e.to_string())
+    };
+
+    Ok(LibraryDiff::new(&load(&old)?, &load(&new)?))
+}
+
+#[tauri::command]
+async fn compare_databases(old: PathBuf, new: PathBuf) -> Result<LibraryDiff, String> {
+    spawn_blocking(move || diff_databases(old, new))
+        .await
+        .unwrap()
+}
+
+#[tauri::command]
+async fn compare_databases_text(old: PathBuf, new: PathBuf) -> Result<String, String> {
+    spawn_blocking(move || diff_databases(old, new).map(|d| d.to_text()))
+        .await
+        .unwrap()
+}
+
+#[tauri::command]
+async fn get_preset_file_info(
+    state: State<'_, AppState>,
+    preset: usize,
+) -> Result<NksFileInfo, String> {
+    let file_name = state
+        .library
+        .load()
+        .presets
+        .get(&preset)
+        .ok_or(UNKNOWN_PRESET)?
+        .file_name
+        .clone();
+
+    spawn_blocking(move || NksFileInfo::read(&file_name).map_err(|e| e.to_string()))
+        .await
+        .unwrap()
+}
+
+#[tauri::command]
+async fn get_preset_parameter_pages(
+    state: State<'_, AppState>,
+    preset: usize,
+) -> Result<Vec<ParameterPage>, String> {
+    let file_name = state
+        .library
+        .load()
+        .presets
+        .get(&preset)
+        .ok_or(UNKNOWN_PRESET)?
+        .file_name
+        .clone();
+
+    spawn_blocking(move || match NksFileInfo::read(&file_name) {
+        Ok(info) => Ok(info.get_parameter_pages()),
+        // .nki, .nksn and other formats simply don't have any pages
+        Err(NksError::NotRiff | NksError::NotNks) => Ok(vec![]),
+        Err(e) => Err(e.to_string()),
+    })
+    .await
+    .unwrap()
+}
+
+#[tauri::command]
+fn get_preview_normalization(state: State<'_, AppState>) -> NormalizationSettings {
+    state.settings.lock().unwrap().normalization
+}
+
+#[tauri::command]
+fn set_preview_normalization(state: State<'_, AppState>, enabled: bool, target: f32) {
+    let mut settings = state.settings.lock().unwrap();
+
+    settings.normalization = NormalizationSettings { enabled, target };
+    settings.save(&get_config_dir());
+}
+
+#[tauri::command]
+fn db_found(state: State<'_, AppState>) -> bool {
+    state.library.load().db_found
+}
+
+#[tauri::command]
+fn is_loading(state: State<'_, AppState>) -> bool {
+    state.loading.lock().unwrap().loading
 }
 
 #[tauri::command]
-fn is_loading(state: State<'_, Mutex<AppState>>) -> bool {
-    state.lock().unwrap().loading
+fn get_loading_progress(state: State<'_, AppState>) -> Option<LoadingProgress> {
+    state.loading.lock().unwrap().progress.clone()
+}
+
+#[tauri::command]
+fn is_refreshing(state: State<'_, AppState>) -> bool {
+    state.loading.lock().unwrap().refreshing
 }
 
 fn get_db3_path() -> PathBuf {
@@ -390,309 +934,353 @@ fn get_db3_path() -> PathBuf {
         ))
 }
 
+fn get_installed_products_locations(settings: &Settings) -> Vec<PathBuf> {
+    if settings.installed_products_folders.is_empty() {
+        vec![installed_products::get_default_location()]
+    } else {
+        settings.installed_products_folders.clone()
+    }
+}
+
+fn get_cache_dir() -> PathBuf {
+    BaseDirs::new().unwrap().cache_dir().join("kk-browser")
+}
+
+fn get_config_dir() -> PathBuf {
+    BaseDirs::new().unwrap().config_dir().join("kk-browser")
+}
+
+// (re)loads the library in the background from all configured sources
+fn load_library(handle: AppHandle) {
+    let (generation, settings) = {
+        let state = handle.state::<AppState>();
+        let generation = state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut loading = state.loading.lock().unwrap();
+
+        // set right away, so the ui never sees the old library as finished
+        loading.loading = true;
+        loading.refreshing = true;
+        (generation, state.settings.lock().unwrap().clone())
+    };
+
+    spawn_blocking(move || {
+        let state = handle.state::<AppState>();
+        let cache_dir = get_cache_dir();
+
+        let mut sources: Vec<Box<dyn LibrarySource>> = vec![Box::new(Db3Source {
+            path: get_db3_path(),
+        })];
+
+        for path in settings.extra_databases {
+            sources.push(Box::new(Db3Source { path }));
+        }
+
+        sources.push(Box::new(FolderSource {
+            folders: settings.library_folders,
+        }));
+
+        let stamps = sources
+            .iter()
+            .map(|s| s.get_stamp())
+            .collect::<Option<Vec<_>>>();
+
+        // serve the cached library right away, even an outdated one is better than waiting
+        if let Some(cache) = LibraryCache::load(&cache_dir) {
+            let current = cache.is_current(&stamps);
+            let (library, infos, reports) = cache.into_library();
+
+            set_library(&state, generation, library, infos, reports, !current);
+
+            if current {
+                validate_presets(&state);
+                return;
+            }
+        }
+
+        let is_latest = || state.generation.load(Ordering::SeqCst) == generation;
+        let report = |progress: LoadingProgress| {
+            if is_latest() {
+                let _ = handle.emit("loading-progress", progress.clone());
+                state.loading.lock().unwrap().progress = Some(progress);
+            }
+        };
+
+        let (library, infos, reports) = source::load_sources(&sources, &report);
+
+        // a newer load has been requested meanwhile, it will write the cache and publish its library
+        if !is_latest() {
+            return;
+        }
+
+        LibraryCache::new(stamps, &library, &infos, &reports).save(&cache_dir);
+        set_library(&state, generation, library, infos, reports, false);
+        validate_presets(&state);
+    });
+}
+
+fn set_library(
+    state: &AppState,
+    generation: u64,
+    mut library: Library,
+    infos: Vec<SourceInfo>,
+    reports: Vec<LoadReport>,
+    refreshing: bool,
+) {
+    library.add_pseudo_entries();
+
+    let (aliases, folders) = {
+        let settings = state.settings.lock().unwrap();
+
+        (
+            VendorAliases::new(&settings.vendor_aliases),
+            get_installed_products_locations(&settings),
+        )
+    };
+    let vendors = library.assign_vendors(&aliases);
+
+    library.assign_product_info(&InstalledProducts::load(&folders));
+    library.intern_product_names();
+
+    // readers still working on the previous library simply keep their snapshot
+    let stored = state
+        .library
+        .store_if(LibraryState::new(library, vendors, infos, reports), || {
+            state.generation.load(Ordering::SeqCst) == generation
+        });
+
+    if !stored {
+        return;
+    }
+
+    let mut loading = state.loading.lock().unwrap();
+
+    loading.loading = false;
+    loading.progress = None;
+    loading.refreshing = refreshing;
+}
+
+#[tauri::command]
+fn get_sources(state: State<'_, AppState>) -> Vec<SourceInfo> {
+    state.library.load().sources.to_vec()
+}
+
+#[tauri::command]
+fn get_load_report(state: State<'_, AppState>) -> Vec<LoadReport> {
+    state.library.load().load_reports.to_vec()
+}
+
+#[tauri::command]
+fn get_extra_databases(state: State<'_, AppState>) -> Vec<PathBuf> {
+    state.settings.lock().unwrap().extra_databases.clone()
+}
+
+#[tauri::command]
+fn set_extra_databases(state: State<'_, AppState>, app: AppHandle, databases: Vec<PathBuf>) {
+    {
+        let mut settings = state.settings.lock().unwrap();
+
+        settings.extra_databases = databases;
+        settings.save(&get_config_dir());
+    }
+
+    load_library(app);
+}
+
+#[tauri::command]
+fn get_library_folders(state: State<'_, AppState>) -> Vec<PathBuf> {
+    state.settings.lock().unwrap().library_folders.clone()
+}
+
+#[tauri::command]
+fn set_library_folders(state: State<'_, AppState>, app: AppHandle, folders: Vec<PathBuf>) {
+    {
+        let mut settings = state.settings.lock().unwrap();
+
+        settings.library_folders = folders;
+        settings.save(&get_config_dir());
+    }
+
+    load_library(app);
+}
+
+#[tauri::command]
+fn get_installed_products_folders(state: State<'_, AppState>) -> Vec<PathBuf> {
+    state
+        .settings
+        .lock()
+        .unwrap()
+        .installed_products_folders
+        .clone()
+}
+
+#[tauri::command]
+fn set_installed_products_folders(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    folders: Vec<PathBuf>,
+) {
+    {
+        let mut settings = state.settings.lock().unwrap();
+
+        settings.installed_products_folders = folders;
+        settings.save(&get_config_dir());
+    }
+
+    load_library(app);
+}
+
+#[tauri::command]
+fn get_vendor_aliases(state: State<'_, AppState>) -> HashMap<String, String> {
+    state.settings.lock().unwrap().vendor_aliases.clone()
+}
+
+#[tauri::command]
+fn set_vendor_aliases(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    aliases: HashMap<String, String>,
+) {
+    {
+        let mut settings = state.settings.lock().unwrap();
+
+        settings.vendor_aliases = aliases;
+        settings.save(&get_config_dir());
+    }
+
+    load_library(app);
+}
+
 #[tauri::command]
 fn get_db_path() -> String {
     get_db3_path().into_os_string().into_string().unwrap()
 }
 
+// resolves kkb:// requests by id, so the webview never needs access to the file system
+fn handle_kkb_request(handle: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let library = handle.state::<AppState>().library.load();
+    let path = match Resource::parse(request.uri()) {
+        Some(Resource::Artwork(product)) => library
+            .products
+            .get(&ProductKey::Id(product))
+            .and_then(|p| p.info.as_ref())
+            .and_then(|i| i.artwork.clone()),
+        Some(Resource::Preview(preset)) => library
+            .presets
+            .get(&preset)
+            .and_then(|p| get_preview_path(&library, p)),
+        None => None,
+    };
+
+    match path {
+        Some(path) => protocol::serve(&path, request),
+        None => protocol::not_found(),
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_opener::init())
+        .register_asynchronous_uri_scheme_protocol(protocol::SCHEME, |ctx, request, responder| {
+            let handle = ctx.app_handle().clone();
+
+            spawn_blocking(move || responder.respond(handle_kkb_request(&handle, &request)));
+        })
         .invoke_handler(tauri::generate_handler![
+            analyze_previews,
+            compare_databases,
+            compare_databases_text,
             db_found,
+            export_library_report,
+            get_bank_children,
+            get_bank_tree,
             get_banks,
             get_categories,
+            get_category_tree,
             get_db_path,
+            get_duplicate_presets,
+            get_extra_databases,
+            get_installed_products_folders,
+            get_library_folders,
+            get_library_report,
+            get_load_report,
+            get_loading_progress,
             get_modes,
+            get_preset_file_info,
+            get_preset_parameter_pages,
+            get_preset_waveform,
             get_presets,
+            get_preview_normalization,
+            get_product_details,
             get_products,
+            get_related_presets,
+            get_similar_presets,
+            get_sources,
+            get_vendor_aliases,
             get_vendors,
             is_loading,
+            is_refreshing,
             play_preset,
+            set_extra_databases,
+            set_installed_products_folders,
+            set_library_folders,
+            set_preview_normalization,
+            set_vendor_aliases,
         ])
         .setup(|app| {
-            let db3_path: PathBuf = get_db3_path();
-            let conn = Connection::open_with_flags(
-                db3_path.as_path(),
-                OpenFlags::SQLITE_OPEN_READ_ONLY
-                    | OpenFlags::SQLITE_OPEN_URI
-                    | OpenFlags::SQLITE_OPEN_NO_MUTEX,
-            )
-            .ok();
-
-            let (sender, mut receiver) = channel::<PathBuf>(10);
-
-            app.manage(Mutex::new(AppState {
-                db_found: conn.is_some(),
-                loading: true,
-                banks: OrderedHashMap::new(),
-                categories: OrderedHashMap::new(),
-                modes: OrderedHashMap::new(),
-                products: MultiKeyMap::new(),
-                presets: OrderedHashMap::new(),
-                preview_sender: sender,
-                vendors: vec![],
-            }));
-
-            if let Some(conn) = conn {
-                let handle = app.app_handle().clone();
-
-                spawn_blocking(move || {
-                    let state = handle.state::<Mutex<AppState>>();
-                    let mut stmt = conn
-                        .prepare("SELECT DISTINCT vendor FROM k_sound_info")
-                        .unwrap();
-
-                    let vendors: Vec<String> = stmt
-                        .query_map([], |row| row.get::<usize, String>(0))
-                        .unwrap()
-                        .filter_map(|v| v.ok())
-                        .collect::<Vec<_>>();
-
-                    let mut banks: OrderedHashMap<usize, Bank> = OrderedHashMap::new();
-
-                    let mut stmt = conn
-                        .prepare("SELECT id, entry1, entry2, entry3 FROM k_bank_chain")
-                        .unwrap();
-
-                    let mut b: Vec<Bank> = stmt
-                        .query_map([], |row| {
-                            Ok(Bank {
-                                id: row.get::<usize, usize>(0).unwrap(),
-                                entry1: row.get::<usize, String>(1).unwrap(),
-                                entry2: row.get::<usize, String>(2).unwrap_or("".into()),
-                                entry3: row.get::<usize, String>(3).unwrap_or("".into()),
-                                presets: HashSet::new(),
-                            })
-                        })
-                        .unwrap()
-                        .filter_map(|b| b.ok())
-                        .collect::<Vec<_>>();
-
-                    b.sort();
-
-                    b.into_iter().for_each(|b| {
-                        banks.insert(b.id, b);
-                    });
-
-                    let mut products: MultiKeyMap<ProductKey, Product> = MultiKeyMap::new();
-
-                    let mut map: HashMap<usize, (String, String, String)> = HashMap::new();
-                    let mut stmt = conn
-                        .prepare(
-                            "\
-SELECT id, path, alias, upid FROM k_content_path",
-                        )
-                        .unwrap();
-
-                    let mut rows = stmt.query([]).unwrap();
-
-                    while let Some(row) = rows.next().unwrap() {
-                        map.insert(
-                            row.get::<usize, usize>(0).unwrap(),
-                            (
-                                row.get::<usize, String>(1).unwrap(),
-                                row.get::<usize, String>(2).unwrap_or("".into()),
-                                row.get::<usize, String>(3).unwrap_or("".into()),
-                            ),
-                        );
-                    }
-
-                    drop(rows);
-
-                    let cmd: String = "\
-SELECT DISTINCT content_path_id, vendor FROM k_sound_info"
-                        .into();
-
-                    stmt = conn.prepare(&cmd).unwrap();
-
-                    let mut rows = stmt.query([]).unwrap();
-
-                    while let Some(row) = rows.next().unwrap() {
-                        let id = row.get::<usize, usize>(0).unwrap();
-
-                        if !map.contains_key(&id) || products.contains_key(&ProductKey::Id(id)) {
-                            continue;
-                        }
-
-                        let keys: Vec<ProductKey> = match map.get(&id).unwrap().2.as_str() {
-                            "" => vec![ProductKey::Id(id)],
-                            other => vec![ProductKey::Id(id), ProductKey::Upid(other.to_string())],
-                        };
-
-                        products.insert_many(
-                            keys,
-                            Product {
-                                id,
-                                name: map.get(&id).unwrap().1.clone(),
-                                vendor: row.get::<usize, String>(1).unwrap_or("".into()),
-                                content_dir: map.get(&id).unwrap().0.clone(),
-                                upid: map.get(&id).unwrap().2.clone(),
-                                presets: HashSet::new(),
-                            },
-                        );
-                    }
-
-                    let mut presets: OrderedHashMap<usize, Preset> = OrderedHashMap::new();
-
-                    let cmd: String = "\
-SELECT \
-    id, name, vendor, comment, content_path_id, file_name, bank_chain_id \
-FROM k_sound_info"
-                        .into();
-
-                    let mut stmt = conn.prepare(&cmd).unwrap();
-
-                    let mut p: Vec<Preset> = stmt
-                        .query_map([], |row| {
-                            Ok(Preset {
-                                id: row.get::<usize, usize>(0).unwrap(),
-                                name: row.get::<usize, String>(1).unwrap_or("".into()),
-                                vendor: row.get::<usize, String>(2).unwrap_or("".into()),
-                                comment: row.get::<usize, String>(3).unwrap_or("".into()),
-                                product_id: ProductKey::Id(row.get::<usize, usize>(4).unwrap()),
-                                product_name: products
-                                    .get(&ProductKey::Id(row.get::<usize, usize>(4).unwrap()))
-                                    .unwrap()
-                                    .name
-                                    .clone(),
-                                file_name: PathBuf::from(&row.get::<usize, String>(5).unwrap()),
-                                categories: HashSet::new(),
-                                modes: HashSet::new(),
-                                bank: row.get::<usize, usize>(6).unwrap_or(0),
-                            })
-                        })
-                        .unwrap()
-                        .filter_map(|p| p.ok())
-                        .collect::<Vec<_>>();
-
-                    p.sort();
-
-                    p.into_iter().for_each(|p| {
-                        products
-                            .get_mut(&p.product_id)
-                            .unwrap()
-                            .presets
-                            .insert(p.id);
-                        if p.bank != 0 {
-                            banks.get_mut(&p.bank).unwrap().presets.insert(p.id);
-                        }
-                        presets.insert(p.id, p);
-                    });
-
-                    let mut categories: OrderedHashMap<usize, Category> = OrderedHashMap::new();
-
-                    let mut stmt = conn
-                        .prepare("SELECT id, category, subcategory, subsubcategory FROM k_category")
-                        .unwrap();
-
-                    let mut c: Vec<Category> = stmt
-                        .query_map([], |row| {
-                            Ok(Category {
-                                id: row.get::<usize, usize>(0).unwrap(),
-                                name: row.get::<usize, String>(1).unwrap(),
-                                subcategory: row.get::<usize, String>(2).unwrap_or("".into()),
-                                subsubcategory: row.get::<usize, String>(3).unwrap_or("".into()),
-                                presets: HashSet::new(),
-                            })
-                        })
-                        .unwrap()
-                        .filter_map(|c| c.ok())
-                        .collect::<Vec<_>>();
-
-                    c.sort();
-
-                    c.into_iter().for_each(|c| {
-                        categories.insert(c.id, c);
-                    });
-
-                    let mut stmt = conn
-                        .prepare("SELECT sound_info_id, category_id FROM k_sound_info_category")
-                        .unwrap();
-
-                    let mut rows = stmt.query([]).unwrap();
-
-                    while let Some(row) = rows.next().unwrap() {
-                        categories
-                            .get_mut(&row.get::<usize, usize>(1).unwrap())
-                            .unwrap()
-                            .presets
-                            .insert(row.get::<usize, usize>(0).unwrap());
-                        presets
-                            .get_mut(&row.get::<usize, usize>(0).unwrap())
-                            .unwrap()
-                            .categories
-                            .insert(row.get::<usize, usize>(1).unwrap());
-                    }
-
-                    let mut modes: OrderedHashMap<usize, Mode> = OrderedHashMap::new();
-
-                    let mut stmt = conn.prepare("SELECT id, name FROM k_mode").unwrap();
-
-                    let mut m: Vec<Mode> = stmt
-                        .query_map([], |row| {
-                            Ok(Mode {
-                                id: row.get::<usize, usize>(0).unwrap(),
-                                name: row.get::<usize, String>(1).unwrap(),
-                                presets: HashSet::new(),
-                            })
-                        })
-                        .unwrap()
-                        .filter_map(|m| m.ok())
-                        .collect::<Vec<_>>();
-
-                    m.sort();
-
-                    m.into_iter().for_each(|m| {
-                        modes.insert(m.id, m);
-                    });
-
-                    let mut stmt = conn
-                        .prepare("SELECT sound_info_id, mode_id FROM k_sound_info_mode")
-                        .unwrap();
-
-                    let mut rows = stmt.query([]).unwrap();
-
-                    while let Some(row) = rows.next().unwrap() {
-                        modes
-                            .get_mut(&row.get::<usize, usize>(1).unwrap())
-                            .unwrap()
-                            .presets
-                            .insert(row.get::<usize, usize>(0).unwrap());
-                        presets
-                            .get_mut(&row.get::<usize, usize>(0).unwrap())
-                            .unwrap()
-                            .modes
-                            .insert(row.get::<usize, usize>(1).unwrap());
-                    }
-
-                    let mut locked_state = state.lock().unwrap();
+            let (sender, mut receiver) = channel::<PreviewRequest>(10);
+
+            app.manage(AppState {
+                analyzing: AtomicBool::new(false),
+                generation: AtomicU64::new(0),
+                features: Mutex::new(FeatureStore::load(&get_cache_dir())),
+                library: Snapshot::new(LibraryState::new(
+                    Library {
+                        banks: OrderedHashMap::new(),
+                        categories: OrderedHashMap::new(),
+                        modes: OrderedHashMap::new(),
+                        products: MultiKeyMap::new(),
+                        presets: OrderedHashMap::new(),
+                        warnings: vec![],
+                        snapshot_time: None,
+                    },
+                    OrderedHashMap::new(),
+                    vec![],
+                    vec![],
+                )),
+                loading: Mutex::new(LoadingState {
+                    loading: true,
+                    ..LoadingState::default()
+                }),
+                playback: PlaybackState {
+                    loudness: Mutex::new(HashMap::new()),
+                    sender,
+                },
+                settings: Mutex::new(Settings::load(&get_config_dir())),
+            });
 
-                    locked_state.vendors = vendors;
-                    locked_state.banks = banks;
-                    locked_state.categories = categories;
-                    locked_state.modes = modes;
-                    locked_state.products = products;
-                    locked_state.presets = presets;
-                    locked_state.loading = false;
-                });
-            }
+            load_library(app.app_handle().clone());
 
             spawn_blocking(move || {
                 let stream_handle = OutputStreamBuilder::open_default_stream().unwrap();
                 let mixer = stream_handle.mixer();
                 let sink = Sink::connect_new(mixer);
 
-                while let Some(path) = receiver.blocking_recv() {
-                    let file = File::open(path).unwrap();
+                while let Some(preview) = receiver.blocking_recv() {
+                    // an unreadable preview is skipped, the audio thread must keep running
+                    let Some(decoder) = File::open(preview.path)
+                        .ok()
+                        .and_then(|f| Decoder::try_from(f).ok())
+                    else {
+                        continue;
+                    };
 
                     if !sink.empty() {
                         sink.clear();
                     }
-                    sink.append(Decoder::try_from(file).unwrap());
+                    sink.append(decoder.amplify(preview.gain));
                     sink.play();
                 }
             });
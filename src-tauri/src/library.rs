@@ -0,0 +1,204 @@
+use crate::{
+    category::{Bank, Category, Mode, NO_ENTRY},
+    installed_products::InstalledProducts,
+    intern::Interner,
+    load_report::LoadWarning,
+    preset::Preset,
+    product::{Product, ProductKey},
+    vendor::{Vendor, VendorAliases},
+};
+use multi_key_map::MultiKeyMap;
+use ordered_hash_map::OrderedHashMap;
+use std::collections::{HashMap, HashSet};
+
+// everything a library source produces, ready to be moved into the app state
+pub struct Library {
+    pub banks: OrderedHashMap<usize, Bank>,
+    pub categories: OrderedHashMap<usize, Category>,
+    pub modes: OrderedHashMap<usize, Mode>,
+    pub products: MultiKeyMap<ProductKey, Product>,
+    pub presets: OrderedHashMap<usize, Preset>,
+    // rows skipped while loading
+    pub warnings: Vec<LoadWarning>,
+    // when the data was read, in seconds since the epoch, for sources that copy a snapshot first
+    pub snapshot_time: Option<u64>,
+}
+
+// puts the pseudo entry in front of the real ones, unless no preset would end up in it
+fn prepend<T: Clone>(entries: &mut OrderedHashMap<usize, T>, entry: T, presets: &HashSet<usize>) {
+    if presets.is_empty() {
+        return;
+    }
+
+    let mut prepended: OrderedHashMap<usize, T> = OrderedHashMap::new();
+
+    prepended.insert(NO_ENTRY, entry);
+
+    for (id, e) in entries.iter() {
+        prepended.insert(*id, e.clone());
+    }
+
+    *entries = prepended;
+}
+
+impl Library {
+    // adds "(No bank)", "(Untyped)" and "(No characteristics)", so presets missing metadata can be filtered as well
+    pub fn add_pseudo_entries(&mut self) {
+        let without = |f: fn(&Preset) -> bool| {
+            self.presets
+                .values()
+                .filter(|p| f(p))
+                .map(|p| p.id)
+                .collect::<HashSet<_>>()
+        };
+
+        let no_bank = without(|p| p.bank == NO_ENTRY);
+        let untyped = without(|p| p.categories.is_empty());
+        let no_characteristics = without(|p| p.modes.is_empty());
+
+        prepend(
+            &mut self.banks,
+            Bank {
+                id: NO_ENTRY,
+                entry1: "(No bank)".into(),
+                entry2: "".into(),
+                entry3: "".into(),
+                presets: no_bank.clone(),
+            },
+            &no_bank,
+        );
+        prepend(
+            &mut self.categories,
+            Category {
+                id: NO_ENTRY,
+                name: "(Untyped)".into(),
+                subcategory: "".into(),
+                subsubcategory: "".into(),
+                presets: untyped.clone(),
+            },
+            &untyped,
+        );
+        prepend(
+            &mut self.modes,
+            Mode {
+                id: NO_ENTRY,
+                name: "(No characteristics)".into(),
+                presets: no_characteristics.clone(),
+            },
+            &no_characteristics,
+        );
+    }
+
+    // merges vendor spelling variants, presets and products get the id and name of the merged vendor
+    pub fn assign_vendors(&mut self, aliases: &VendorAliases) -> OrderedHashMap<usize, Vendor> {
+        let mut vendors: HashMap<usize, Vendor> = HashMap::new();
+        // presets per vendor of every product
+        let mut product_vendors: HashMap<usize, HashMap<usize, usize>> = HashMap::new();
+
+        let mut add_vendor = |name: &str| {
+            let name = aliases.resolve(name);
+            let id = Vendor::get_id(&name);
+
+            vendors.entry(id).or_insert_with(|| Vendor {
+                id,
+                name: name.into(),
+                presets: HashSet::new(),
+                products: HashSet::new(),
+            });
+
+            id
+        };
+
+        let ids = self
+            .presets
+            .values()
+            .map(|p| (p.id, add_vendor(&p.vendor)))
+            .collect::<Vec<_>>();
+
+        let product_ids = self
+            .products
+            .values()
+            .map(|p| (p.id, add_vendor(&p.vendor)))
+            .collect::<Vec<_>>();
+
+        for (preset, vendor) in ids {
+            let Some(preset) = self.presets.get_mut(&preset) else {
+                continue;
+            };
+            let v = vendors.get_mut(&vendor).unwrap();
+
+            v.presets.insert(preset.id);
+            preset.vendor = v.name.clone();
+            preset.vendor_id = vendor;
+
+            if let Some(product) = self.products.get(&preset.product_id) {
+                *product_vendors
+                    .entry(product.id)
+                    .or_default()
+                    .entry(vendor)
+                    .or_default() += 1;
+            }
+        }
+
+        for (product, fallback) in product_ids {
+            // the vendor most presets agree on, not just the first row seen
+            let vendor = product_vendors
+                .get(&product)
+                .and_then(|counts| {
+                    counts
+                        .iter()
+                        .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+                        .map(|(v, _)| *v)
+                })
+                .unwrap_or(fallback);
+            let v = vendors.get_mut(&vendor).unwrap();
+            let product = self.products.get_mut(&ProductKey::Id(product)).unwrap();
+
+            v.products.insert(product.id);
+            product.vendor = v.name.clone();
+            product.vendor_id = vendor;
+        }
+
+        let mut sorted: Vec<Vendor> = vendors
+            .into_values()
+            .filter(|v| !v.presets.is_empty() || !v.products.is_empty())
+            .collect::<Vec<_>>();
+        let mut result: OrderedHashMap<usize, Vendor> = OrderedHashMap::new();
+
+        sorted.sort();
+
+        sorted.into_iter().for_each(|v| {
+            result.insert(v.id, v);
+        });
+
+        result
+    }
+
+    // presets of a product all point to the same name instead of holding their own copy
+    pub fn intern_product_names(&mut self) {
+        let mut interner = Interner::default();
+        let ids = self.presets.keys().copied().collect::<Vec<_>>();
+
+        for id in ids {
+            let preset = self.presets.get_mut(&id).unwrap();
+
+            preset.product_name = interner.intern(&preset.product_name);
+        }
+    }
+
+    // attaches what the installed_products descriptors know about each product
+    pub fn assign_product_info(&mut self, installed: &InstalledProducts) {
+        let ids = self.products.values().map(|p| p.id).collect::<Vec<_>>();
+        let preview_library = installed.get_preview_library();
+
+        for id in ids {
+            let product = self.products.get_mut(&ProductKey::Id(id)).unwrap();
+
+            product.info = installed.find(product).cloned();
+            product.preview_dir = preview_library
+                .as_ref()
+                .filter(|_| !product.upid.is_empty())
+                .map(|l| l.join("Samples").join(&product.upid));
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// inconsistencies found while loading, the affected rows are skipped instead of failing the whole source
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LoadWarning {
+    // the database predates these columns, they are read as empty
+    OlderSchema {
+        missing: Vec<String>,
+    },
+    // a required column is NULL or can't be read, the row is skipped
+    InvalidValue {
+        table: String,
+        column: String,
+        id: Option<usize>,
+    },
+    // the preset is kept, named after its file instead
+    NullName {
+        preset: usize,
+    },
+    MissingContentPath {
+        preset: usize,
+        content_path: usize,
+    },
+    UnknownBank {
+        preset: usize,
+        bank: usize,
+    },
+    // a row of a join table points to a preset or entry that doesn't exist
+    OrphanJoin {
+        table: String,
+        preset: usize,
+        entry: usize,
+    },
+    UnreadableFile {
+        path: PathBuf,
+        error: String,
+    },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LoadReport {
+    pub source: usize,
+    pub name: String,
+    pub warnings: Vec<LoadWarning>,
+}
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+// previews quieter than this are considered silent and never amplified
+const SILENCE_THRESHOLD: f32 = -70.0;
+// upper bound for the applied gain, quiet previews would otherwise blow up their noise floor
+const MAX_GAIN: f32 = 24.0;
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct NormalizationSettings {
+    pub enabled: bool,
+    pub target: f32,
+}
+
+impl Default for NormalizationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target: -18.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Loudness {
+    // RMS level in dBFS
+    pub rms: f32,
+    // linear sample peak
+    pub peak: f32,
+}
+
+impl Loudness {
+    pub fn measure<I: IntoIterator<Item = f32>>(samples: I) -> Option<Self> {
+        let mut sum: f64 = 0.0;
+        let mut count: usize = 0;
+        let mut peak: f32 = 0.0;
+
+        for sample in samples {
+            sum += (sample as f64) * (sample as f64);
+            peak = peak.max(sample.abs());
+            count += 1;
+        }
+
+        if count == 0 {
+            return None;
+        }
+
+        let rms = (sum / count as f64).sqrt() as f32;
+
+        Some(Self {
+            rms: if rms > 0.0 {
+                20.0 * rms.log10()
+            } else {
+                f32::NEG_INFINITY
+            },
+            peak,
+        })
+    }
+
+    pub fn gain(&self, target: f32) -> f32 {
+        if self.rms < SILENCE_THRESHOLD {
+            return 1.0;
+        }
+
+        let mut gain = 10f32.powf((target - self.rms).min(MAX_GAIN) / 20.0);
+
+        // never push the preview into clipping
+        if self.peak > 0.0 && self.peak * gain > 1.0 {
+            gain = 1.0 / self.peak;
+        }
+
+        gain
+    }
+}
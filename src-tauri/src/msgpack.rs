@@ -0,0 +1,251 @@
+// minimal MessagePack decoder, just enough to read the metadata chunks of NKS files
+
+// NKS metadata nests a few levels at most, this keeps corrupted files from exhausting the stack
+const MAX_DEPTH: usize = 64;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bin(Vec<u8>),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+}
+
+impl Value {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => entries
+                .iter()
+                .find(|(k, _)| k.as_str() == Some(key))
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&[(Value, Value)]> {
+        match self {
+            Value::Map(m) => Some(m),
+            _ => None,
+        }
+    }
+
+    // array of strings, anything else in the array is skipped
+    pub fn as_strings(&self) -> Vec<String> {
+        self.as_array()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|v| v.as_str().map(String::from))
+            .collect::<Vec<_>>()
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+
+        self.pos += len;
+
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn uint(&mut self, len: usize) -> Option<u64> {
+        Some(
+            self.take(len)?
+                .iter()
+                .fold(0u64, |acc, b| (acc << 8) | *b as u64),
+        )
+    }
+
+    fn int(&mut self, len: usize) -> Option<i64> {
+        let value = self.uint(len)?;
+        let shift = 64 - len * 8;
+
+        // sign extend
+        Some(((value << shift) as i64) >> shift)
+    }
+
+    fn str(&mut self, len: usize) -> Option<Value> {
+        Some(Value::Str(
+            String::from_utf8_lossy(self.take(len)?).into_owned(),
+        ))
+    }
+
+    fn array(&mut self, len: usize, depth: usize) -> Option<Value> {
+        (0..len)
+            .map(|_| self.value(depth + 1))
+            .collect::<Option<Vec<_>>>()
+            .map(Value::Array)
+    }
+
+    fn map(&mut self, len: usize, depth: usize) -> Option<Value> {
+        (0..len)
+            .map(|_| Some((self.value(depth + 1)?, self.value(depth + 1)?)))
+            .collect::<Option<Vec<_>>>()
+            .map(Value::Map)
+    }
+
+    fn value(&mut self, depth: usize) -> Option<Value> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        let marker = self.u8()?;
+
+        match marker {
+            0x00..=0x7f => Some(Value::Int(marker as i64)),
+            0x80..=0x8f => self.map((marker & 0x0f) as usize, depth),
+            0x90..=0x9f => self.array((marker & 0x0f) as usize, depth),
+            0xa0..=0xbf => self.str((marker & 0x1f) as usize),
+            0xc0 => Some(Value::Nil),
+            0xc2 => Some(Value::Bool(false)),
+            0xc3 => Some(Value::Bool(true)),
+            0xc4..=0xc6 => {
+                let len = self.uint(1 << (marker - 0xc4))? as usize;
+
+                Some(Value::Bin(self.take(len)?.to_vec()))
+            }
+            // ext types carry nothing we need, skip them
+            0xc7..=0xc9 => {
+                let len = self.uint(1 << (marker - 0xc7))? as usize;
+
+                self.take(len + 1)?;
+                Some(Value::Nil)
+            }
+            0xca => Some(Value::Float(f32::from_bits(self.uint(4)? as u32) as f64)),
+            0xcb => Some(Value::Float(f64::from_bits(self.uint(8)?))),
+            0xcc..=0xcf => {
+                let value = self.uint(1 << (marker - 0xcc))?;
+
+                Some(Value::Int(i64::try_from(value).unwrap_or(i64::MAX)))
+            }
+            0xd0..=0xd3 => Some(Value::Int(self.int(1 << (marker - 0xd0))?)),
+            0xd4..=0xd8 => {
+                self.take((1 << (marker - 0xd4)) + 1)?;
+                Some(Value::Nil)
+            }
+            0xd9..=0xdb => {
+                let len = self.uint(1 << (marker - 0xd9))? as usize;
+
+                self.str(len)
+            }
+            0xdc | 0xdd => {
+                let len = self.uint(2 << (marker - 0xdc))? as usize;
+
+                self.array(len, depth)
+            }
+            0xde | 0xdf => {
+                let len = self.uint(2 << (marker - 0xde))? as usize;
+
+                self.map(len, depth)
+            }
+            0xe0..=0xff => Some(Value::Int((marker as i8) as i64)),
+            _ => None,
+        }
+    }
+}
+
+pub fn decode(data: &[u8]) -> Option<Value> {
+    Reader { data, pos: 0 }.value(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_scalars() {
+        assert_eq!(decode(&[0x05]), Some(Value::Int(5)));
+        assert_eq!(decode(&[0xff]), Some(Value::Int(-1)));
+        assert_eq!(decode(&[0xd1, 0xff, 0x00]), Some(Value::Int(-256)));
+        assert_eq!(decode(&[0xcd, 0x04, 0xd2]), Some(Value::Int(1234)));
+        assert_eq!(decode(&[0xc3]), Some(Value::Bool(true)));
+        assert_eq!(decode(&[0xc0]), Some(Value::Nil));
+        assert_eq!(
+            decode(&[0xa3, b'a', b'b', b'c']),
+            Some(Value::Str("abc".into()))
+        );
+    }
+
+    #[test]
+    fn decodes_containers() {
+        // {"a": [1, "b"]}
+        let value = decode(&[0x81, 0xa1, b'a', 0x92, 0x01, 0xa1, b'b']).unwrap();
+
+        assert_eq!(
+            value.get("a").and_then(|v| v.as_array()),
+            Some(&[Value::Int(1), Value::Str("b".into())][..])
+        );
+        assert_eq!(value.get("a").unwrap().as_strings(), vec!["b".to_string()]);
+        assert_eq!(value.get("c"), None);
+    }
+
+    #[test]
+    fn rejects_bad_markers() {
+        // 0xc1 is never used
+        assert_eq!(decode(&[0xc1]), None);
+        assert_eq!(decode(&[0x92, 0x01, 0xc1]), None);
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        assert_eq!(decode(&[]), None);
+        assert_eq!(decode(&[0xa3, b'a']), None);
+        assert_eq!(decode(&[0x92, 0x01]), None);
+        assert_eq!(decode(&[0xdd, 0xff, 0xff, 0xff, 0xff]), None);
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let nested = |levels: usize| {
+            let mut data = vec![0x91; levels];
+
+            data.push(0xc0);
+            data
+        };
+
+        assert!(decode(&nested(MAX_DEPTH)).is_some());
+        assert_eq!(decode(&nested(MAX_DEPTH + 1)), None);
+        assert_eq!(decode(&vec![0x81; 100_000]), None);
+    }
+}
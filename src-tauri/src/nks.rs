@@ -0,0 +1,508 @@
+use crate::msgpack::{self, Value};
+use serde::Serialize;
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+// metadata chunks are tiny, anything larger than this is a corrupted file
+const MAX_METADATA_SIZE: u64 = 16 * 1024 * 1024;
+const KNOBS_PER_PAGE: usize = 8;
+
+#[derive(Debug)]
+pub enum NksError {
+    Io(io::Error),
+    NotRiff,
+    NotNks,
+    InvalidChunk(String),
+}
+
+impl fmt::Display for NksError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NksError::Io(e) => write!(f, "unable to read preset file: {}", e),
+            NksError::NotRiff => write!(f, "not a RIFF file"),
+            NksError::NotNks => write!(f, "not an NKS preset file"),
+            NksError::InvalidChunk(id) => write!(f, "invalid {} chunk", id),
+        }
+    }
+}
+
+impl From<io::Error> for NksError {
+    fn from(e: io::Error) -> Self {
+        NksError::Io(e)
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(tag = "format", rename_all = "snake_case")]
+pub enum PluginId {
+    Vst { magic: i64 },
+    Vst3 { uid: Vec<i64> },
+    Other { key: String },
+}
+
+#[derive(Clone, Serialize)]
+pub struct Controller {
+    pub id: Option<i64>,
+    pub name: String,
+    // only set on the first controller of a section
+    pub section: String,
+    pub autoname: bool,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ControllerPage {
+    // None for unassigned knobs
+    pub knobs: Vec<Option<Controller>>,
+}
+
+impl ControllerPage {
+    pub fn to_parameter_page(&self, index: usize) -> ParameterPage {
+        let mut section = String::new();
+        let mut sections: Vec<String> = vec![];
+
+        let mut knobs: Vec<Knob> = self
+            .knobs
+            .iter()
+            .map(|knob| match knob {
+                Some(controller) => {
+                    // sections continue until the next knob starts a new one
+                    if !controller.section.is_empty() {
+                        section = controller.section.clone();
+                        sections.push(section.clone());
+                    }
+
+                    Knob {
+                        name: controller.name.clone(),
+                        section: section.clone(),
+                    }
+                }
+                None => Knob {
+                    name: String::new(),
+                    section: String::new(),
+                },
+            })
+            .collect::<Vec<_>>();
+
+        knobs.resize(
+            KNOBS_PER_PAGE.max(knobs.len()),
+            Knob {
+                name: String::new(),
+                section: String::new(),
+            },
+        );
+
+        ParameterPage {
+            name: if sections.is_empty() {
+                format!("Page {}", index + 1)
+            } else {
+                sections.join(" / ")
+            },
+            sections,
+            knobs,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct Knob {
+    // empty for unassigned knobs
+    pub name: String,
+    pub section: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ParameterPage {
+    pub name: String,
+    pub sections: Vec<String>,
+    pub knobs: Vec<Knob>,
+}
+
+#[derive(Clone, Default, Serialize)]
+pub struct NksFileInfo {
+    pub name: String,
+    pub author: String,
+    pub vendor: String,
+    pub comment: String,
+    pub device_type: String,
+    pub uuid: String,
+    pub bank_chain: Vec<String>,
+    pub types: Vec<Vec<String>>,
+    pub modes: Vec<String>,
+    pub plugin_id: Option<PluginId>,
+    pub pages: Vec<ControllerPage>,
+}
+
+impl NksFileInfo {
+    pub fn read(path: &Path) -> Result<Self, NksError> {
+        Self::parse(&mut BufReader::new(File::open(path)?))
+    }
+
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, NksError> {
+        let mut header = [0u8; 12];
+
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| NksError::NotRiff)?;
+
+        if &header[0..4] != b"RIFF" {
+            return Err(NksError::NotRiff);
+        }
+
+        if &header[8..12] != b"NIKS" {
+            return Err(NksError::NotNks);
+        }
+
+        let mut info = Self::default();
+        let mut found = false;
+        let mut chunk_header = [0u8; 8];
+
+        while reader.read_exact(&mut chunk_header).is_ok() {
+            let id = String::from_utf8_lossy(&chunk_header[0..4]).into_owned();
+            let size = u32::from_le_bytes(chunk_header[4..8].try_into().unwrap()) as u64;
+
+            match id.as_str() {
+                "NISI" | "NICA" | "PLID" => {
+                    if size > MAX_METADATA_SIZE {
+                        return Err(NksError::InvalidChunk(id));
+                    }
+
+                    let mut data = vec![0u8; size as usize];
+
+                    reader.read_exact(&mut data)?;
+
+                    // every metadata chunk starts with a 4 byte version, followed by the payload
+                    let value = data
+                        .get(4..)
+                        .and_then(msgpack::decode)
+                        .ok_or_else(|| NksError::InvalidChunk(id.clone()))?;
+
+                    match id.as_str() {
+                        "NISI" => {
+                            info.read_sound_info(&value);
+                            found = true;
+                        }
+                        "NICA" => info.pages = read_controller_pages(&value),
+                        _ => info.plugin_id = read_plugin_id(&value),
+                    }
+                }
+                // plugin state and everything else, usually the bulk of the file
+                _ => {
+                    reader.seek(SeekFrom::Current(size as i64))?;
+                }
+            }
+
+            // chunks are padded to an even size
+            if size % 2 == 1 {
+                reader.seek(SeekFrom::Current(1))?;
+            }
+        }
+
+        if !found {
+            return Err(NksError::InvalidChunk("NISI".into()));
+        }
+
+        Ok(info)
+    }
+
+    pub fn get_parameter_pages(&self) -> Vec<ParameterPage> {
+        self.pages
+            .iter()
+            .enumerate()
+            .map(|(i, p)| p.to_parameter_page(i))
+            .collect::<Vec<_>>()
+    }
+
+    fn read_sound_info(&mut self, value: &Value) {
+        let string = |key: &str| {
+            value
+                .get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+
+        self.name = string("name");
+        self.author = string("author");
+        self.vendor = string("vendor");
+        self.comment = string("comment");
+        self.device_type = string("deviceType");
+        self.uuid = string("uuid");
+        self.bank_chain = value
+            .get("bankchain")
+            .map(|v| v.as_strings())
+            .unwrap_or_default();
+        self.types = value
+            .get("types")
+            .and_then(|v| v.as_array())
+            .unwrap_or_default()
+            .iter()
+            .map(|t| t.as_strings())
+            .collect::<Vec<_>>();
+        self.modes = value
+            .get("modes")
+            .map(|v| v.as_strings())
+            .unwrap_or_default();
+    }
+}
+
+fn read_controller_pages(value: &Value) -> Vec<ControllerPage> {
+    value
+        .get("ni8")
+        .and_then(|v| v.as_array())
+        .unwrap_or_default()
+        .iter()
+        .map(|page| ControllerPage {
+            knobs: page
+                .as_array()
+                .unwrap_or_default()
+                .iter()
+                .map(|knob| {
+                    // unassigned knobs are stored as empty maps
+                    let name = knob.get("name").and_then(|n| n.as_str())?;
+
+                    Some(Controller {
+                        id: knob.get("id").and_then(|i| i.as_i64()),
+                        name: name.to_string(),
+                        section: knob
+                            .get("section")
+                            .and_then(|s| s.as_str())
+                            .unwrap_or_default()
+                            .to_string(),
+                        autoname: knob
+                            .get("autoname")
+                            .and_then(|a| a.as_bool())
+                            .unwrap_or(false),
+                    })
+                })
+                .collect::<Vec<_>>(),
+        })
+        .collect::<Vec<_>>()
+}
+
+fn read_plugin_id(value: &Value) -> Option<PluginId> {
+    let (key, id) = value.as_map()?.first()?;
+
+    match key.as_str()? {
+        "VST.magic" => Some(PluginId::Vst {
+            magic: id.as_i64()?,
+        }),
+        "VST3.uid" => Some(PluginId::Vst3 {
+            uid: id
+                .as_array()?
+                .iter()
+                .filter_map(|i| i.as_i64())
+                .collect::<Vec<_>>(),
+        }),
+        other => Some(PluginId::Other {
+            key: other.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn str(s: &str) -> Vec<u8> {
+        let mut data = vec![0xa0 | s.len() as u8];
+
+        data.extend(s.as_bytes());
+        data
+    }
+
+    fn array(items: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = vec![0x90 | items.len() as u8];
+
+        items.iter().for_each(|i| data.extend(i));
+        data
+    }
+
+    fn map(entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut data = vec![0x80 | entries.len() as u8];
+
+        for (key, value) in entries {
+            data.extend(str(key));
+            data.extend(value);
+        }
+
+        data
+    }
+
+    fn chunk(id: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut data = id.to_vec();
+
+        data.extend((payload.len() as u32 + 4).to_le_bytes());
+        data.extend([1, 0, 0, 0]);
+        data.extend(payload);
+
+        if data.len() % 2 == 1 {
+            data.push(0);
+        }
+
+        data
+    }
+
+    fn riff(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body = chunks.concat();
+        let mut data = b"RIFF".to_vec();
+
+        data.extend((body.len() as u32 + 4).to_le_bytes());
+        data.extend(b"NIKS");
+        data.extend(body);
+        data
+    }
+
+    fn sound_info() -> Vec<u8> {
+        chunk(
+            b"NISI",
+            &map(&[
+                ("name", str("Warm Pad")),
+                ("vendor", str("Native Instruments")),
+                ("bankchain", array(&[str("Massive"), str("Factory")])),
+                ("types", array(&[array(&[str("Synth Pad"), str("Warm")])])),
+                ("modes", array(&[str("Analog")])),
+            ]),
+        )
+    }
+
+    fn parse(data: Vec<u8>) -> Result<NksFileInfo, NksError> {
+        NksFileInfo::parse(&mut Cursor::new(data))
+    }
+
+    #[test]
+    fn reads_metadata_chunks() {
+        let controllers = chunk(
+            b"NICA",
+            &map(&[(
+                "ni8",
+                array(&[array(&[
+                    map(&[
+                        ("name", str("Cutoff")),
+                        ("section", str("Filter")),
+                        ("id", vec![0x01]),
+                    ]),
+                    map(&[]),
+                    map(&[("name", str("Reso")), ("autoname", vec![0xc3])]),
+                ])]),
+            )]),
+        );
+        let plugin = chunk(b"PLID", &map(&[("VST.magic", vec![0xcd, 0x04, 0xd2])]));
+        // odd sized state in between, the padding byte has to be skipped
+        let mut state = b"PCHK".to_vec();
+
+        state.extend(3u32.to_le_bytes());
+        state.extend([1, 2, 3, 0]);
+
+        let info = parse(riff(&[sound_info(), state, controllers, plugin])).unwrap();
+
+        assert_eq!(info.name, "Warm Pad");
+        assert_eq!(info.vendor, "Native Instruments");
+        assert_eq!(info.bank_chain, vec!["Massive", "Factory"]);
+        assert_eq!(info.types, vec![vec!["Synth Pad", "Warm"]]);
+        assert_eq!(info.modes, vec!["Analog"]);
+        assert!(matches!(
+            info.plugin_id,
+            Some(PluginId::Vst { magic: 1234 })
+        ));
+        assert_eq!(info.pages.len(), 1);
+
+        let knobs = &info.pages[0].knobs;
+
+        assert_eq!(knobs.len(), 3);
+        assert_eq!(knobs[0].as_ref().and_then(|k| k.id), Some(1));
+        assert!(knobs[1].is_none());
+        assert!(knobs[2].as_ref().is_some_and(|k| k.autoname));
+    }
+
+    #[test]
+    fn rejects_truncated_header() {
+        assert!(matches!(
+            parse(b"RIFF\x10\0".to_vec()),
+            Err(NksError::NotRiff)
+        ));
+        assert!(matches!(
+            parse(b"RIFF\x10\0\0\0WAVE".to_vec()),
+            Err(NksError::NotNks)
+        ));
+    }
+
+    #[test]
+    fn requires_sound_info() {
+        assert!(matches!(
+            parse(riff(&[])),
+            Err(NksError::InvalidChunk(id)) if id == "NISI"
+        ));
+    }
+
+    #[test]
+    fn rejects_oversized_chunks() {
+        let mut oversized = b"NICA".to_vec();
+
+        oversized.extend((MAX_METADATA_SIZE as u32 + 1).to_le_bytes());
+
+        assert!(matches!(
+            parse(riff(&[sound_info(), oversized])),
+            Err(NksError::InvalidChunk(id)) if id == "NICA"
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_metadata() {
+        assert!(matches!(
+            parse(riff(&[chunk(b"NISI", &[0xc1])])),
+            Err(NksError::InvalidChunk(id)) if id == "NISI"
+        ));
+        assert!(matches!(
+            parse(riff(&[chunk(b"NISI", &vec![0x91; 1000])])),
+            Err(NksError::InvalidChunk(id)) if id == "NISI"
+        ));
+    }
+
+    #[test]
+    fn pads_pages_to_full_knob_rows() {
+        let knob = |name: &str, section: &str| {
+            Some(Controller {
+                id: None,
+                name: name.into(),
+                section: section.into(),
+                autoname: false,
+            })
+        };
+        let info = NksFileInfo {
+            pages: vec![
+                ControllerPage {
+                    knobs: vec![
+                        knob("Cutoff", "Filter"),
+                        knob("Reso", ""),
+                        None,
+                        knob("Attack", "Envelope"),
+                    ],
+                },
+                ControllerPage {
+                    knobs: (0..KNOBS_PER_PAGE + 2)
+                        .map(|i| knob(&format!("Macro {}", i), ""))
+                        .collect::<Vec<_>>(),
+                },
+            ],
+            ..Default::default()
+        };
+
+        let pages = info.get_parameter_pages();
+
+        assert_eq!(pages[0].name, "Filter / Envelope");
+        assert_eq!(pages[0].knobs.len(), KNOBS_PER_PAGE);
+        assert_eq!(pages[0].knobs[1].section, "Filter");
+        assert_eq!(pages[0].knobs[2].name, "");
+        assert_eq!(pages[0].knobs[3].section, "Envelope");
+        assert_eq!(pages[0].knobs[KNOBS_PER_PAGE - 1].name, "");
+        // sections don't carry over to the next page
+        assert_eq!(pages[1].name, "Page 2");
+        assert_eq!(pages[1].knobs.len(), KNOBS_PER_PAGE + 2);
+        assert_eq!(pages[1].knobs[0].section, "");
+    }
+}
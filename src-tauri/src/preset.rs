@@ -1,25 +1,153 @@
-use crate::product::ProductKey;
+use crate::{
+    category::NO_ENTRY,
+    product::{Product, ProductKey},
+};
 use serde::Serialize;
 use std::{
     cmp::Ordering,
     collections::HashSet,
     hash::{Hash, Hasher},
     path::PathBuf,
+    sync::Arc,
 };
 
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PresetStatus {
+    // not validated yet
+    #[default]
+    Unknown,
+    Ok,
+    MissingFile,
+    MissingContentDir,
+}
+
+impl PresetStatus {
+    pub fn is_broken(&self) -> bool {
+        matches!(
+            self,
+            PresetStatus::MissingFile | PresetStatus::MissingContentDir
+        )
+    }
+}
+
 #[derive(Clone, Serialize)]
 pub struct Preset {
     pub name: String,
-    pub vendor: String,
+    pub vendor: Arc<str>,
+    // assigned once all sources are merged, see Library::assign_vendors
+    pub vendor_id: usize,
     pub comment: String,
     #[serde(skip)]
     pub product_id: ProductKey,
-    pub product_name: String,
+    // shared with all presets of the product, see Library::intern_product_names
+    pub product_name: Arc<str>,
     pub id: usize,
     pub file_name: PathBuf,
     pub categories: HashSet<usize>,
     pub modes: HashSet<usize>,
     pub bank: usize,
+    pub status: PresetStatus,
+    // index of the library source this preset was loaded from
+    pub source: usize,
+}
+
+fn jaccard<T: Eq + Hash>(a: &HashSet<T>, b: &HashSet<T>) -> f32 {
+    if a.is_empty() && b.is_empty() {
+        return 0.0;
+    }
+
+    a.intersection(b).count() as f32 / a.union(b).count() as f32
+}
+
+impl Preset {
+    pub fn check_status(&self, product: &Product) -> PresetStatus {
+        if !product.content_dir.is_empty() && !PathBuf::from(&product.content_dir).exists() {
+            PresetStatus::MissingContentDir
+        } else if !self.file_name.exists() {
+            PresetStatus::MissingFile
+        } else {
+            PresetStatus::Ok
+        }
+    }
+
+    pub fn has_category(&self, category: usize) -> bool {
+        if category == NO_ENTRY {
+            self.categories.is_empty()
+        } else {
+            self.categories.contains(&category)
+        }
+    }
+
+    pub fn has_mode(&self, mode: usize) -> bool {
+        if mode == NO_ENTRY {
+            self.modes.is_empty()
+        } else {
+            self.modes.contains(&mode)
+        }
+    }
+
+    pub fn get_name_tokens(&self) -> HashSet<String> {
+        self.name
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_lowercase())
+            .collect::<HashSet<_>>()
+    }
+
+    // weighted metadata overlap between two presets, 0 means nothing in common
+    // tokens are the name tokens of self, so comparing against many presets tokenizes it only once
+    pub fn similarity(&self, tokens: &HashSet<String>, other: &Preset) -> f32 {
+        let mut score = 3.0 * jaccard(&self.categories, &other.categories)
+            + 2.0 * jaccard(&self.modes, &other.modes)
+            + jaccard(tokens, &other.get_name_tokens());
+
+        if self.bank != 0 && self.bank == other.bank {
+            score += 1.0;
+        }
+
+        if self.product_id == other.product_id {
+            score += 1.0;
+        }
+
+        score
+    }
+
+    pub fn get_preview_path(&self, product: &Product) -> Option<PathBuf> {
+        if self
+            .file_name
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("wav"))
+            && self.file_name.exists()
+        {
+            return Some(self.file_name.clone());
+        }
+
+        let preview_name = format!("{}.ogg", self.file_name.file_name()?.to_string_lossy());
+        let p = self
+            .file_name
+            .parent()?
+            .join(".previews")
+            .join(&preview_name);
+
+        if p.exists() {
+            return Some(p);
+        }
+
+        // previews of NI instruments live in the preview library, mirroring the content dir
+        let relative = self.file_name.strip_prefix(&product.content_dir).ok()?;
+
+        Some(
+            product
+                .preview_dir
+                .as_ref()?
+                .join(relative)
+                .parent()?
+                .join(".previews")
+                .join(preview_name),
+        )
+        .filter(|p| p.exists())
+    }
 }
 
 impl Ord for Preset {
@@ -1,11 +1,14 @@
-use serde::Serialize;
+use crate::installed_products::ProductInfo;
+use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
     collections::HashSet,
     hash::{Hash, Hasher},
+    path::PathBuf,
+    sync::Arc,
 };
 
-#[derive(Clone, Eq, PartialEq, Hash)]
+#[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum ProductKey {
     Id(usize),
     Upid(String),
@@ -17,16 +20,42 @@ pub struct Product {
     pub name: String,
     #[serde(skip)]
     pub content_dir: String,
-    pub vendor: String,
+    pub vendor: Arc<str>,
+    pub vendor_id: usize,
     #[serde(skip)]
     pub upid: String,
     #[serde(skip)]
     pub presets: HashSet<usize>,
+    // the folder within the preview library mirroring the content dir, see Library::assign_product_info
+    #[serde(skip)]
+    pub preview_dir: Option<PathBuf>,
+    // found in the installed_products descriptors, see Library::assign_product_info
+    pub info: Option<ProductInfo>,
+}
+
+// everything known about a single product, including what the facet leaves out
+#[derive(Serialize)]
+pub struct ProductDetails {
+    #[serde(flatten)]
+    pub product: Product,
+    pub content_dir: String,
+    pub upid: String,
+    pub presets: usize,
+}
+
+impl Product {
+    // the name from the descriptor reads better than the content path alias
+    pub fn get_display_name(&self) -> &str {
+        self.info
+            .as_ref()
+            .map(|i| i.name.as_str())
+            .unwrap_or(&self.name)
+    }
 }
 
 impl Ord for Product {
     fn cmp(&self, other: &Self) -> Ordering {
-        natord::compare_ignore_case(&self.name, &other.name)
+        natord::compare_ignore_case(self.get_display_name(), other.get_display_name())
     }
 }
 
@@ -0,0 +1,61 @@
+use serde::Serialize;
+
+// reporting every single row would cost more than loading it
+const REPORT_INTERVAL: usize = 500;
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadingPhase {
+    Banks,
+    ContentPaths,
+    Presets,
+    Categories,
+    Modes,
+    // preset files of a folder source
+    Files,
+}
+
+#[derive(Clone, Serialize)]
+pub struct LoadingProgress {
+    // index of the source being loaded, out of all configured sources
+    pub source: usize,
+    pub sources: usize,
+    pub phase: LoadingPhase,
+    pub processed: usize,
+    pub total: usize,
+}
+
+pub type ProgressCallback<'a> = &'a dyn Fn(LoadingPhase, usize, usize);
+
+// counts the rows of a single phase and forwards them to the callback from time to time
+pub struct Progress<'a> {
+    callback: ProgressCallback<'a>,
+    phase: LoadingPhase,
+    processed: usize,
+    total: usize,
+}
+
+impl<'a> Progress<'a> {
+    pub fn new(callback: ProgressCallback<'a>, phase: LoadingPhase, total: usize) -> Self {
+        callback(phase, 0, total);
+
+        Self {
+            callback,
+            phase,
+            processed: 0,
+            total,
+        }
+    }
+
+    pub fn step(&mut self) {
+        self.processed += 1;
+
+        if self.processed.is_multiple_of(REPORT_INTERVAL) {
+            (self.callback)(self.phase, self.processed, self.total);
+        }
+    }
+
+    pub fn finish(self) {
+        (self.callback)(self.phase, self.processed, self.total.max(self.processed));
+    }
+}
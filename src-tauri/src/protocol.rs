@@ -0,0 +1,176 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+use tauri::http::{Request, Response, StatusCode, Uri, header};
+
+pub const SCHEME: &str = "kkb";
+
+// what the webview may ask for, files are only ever looked up by id
+pub enum Resource {
+    Artwork(usize),
+    Preview(usize),
+}
+
+impl Resource {
+    // kkb://artwork/<product> on Linux and macOS, http://kkb.localhost/artwork/<product> on Windows
+    pub fn parse(uri: &Uri) -> Option<Self> {
+        let path = uri.path().replace("%2F", "/").replace("%2f", "/");
+        let mut parts = uri
+            .host()
+            .filter(|h| *h != "localhost" && !h.starts_with(SCHEME))
+            .into_iter()
+            .chain(path.split('/'))
+            .filter(|p| !p.is_empty());
+
+        let kind = parts.next()?;
+        let id = parts.next()?.parse::<usize>().ok()?;
+
+        match kind {
+            "artwork" => Some(Resource::Artwork(id)),
+            "preview" => Some(Resource::Preview(id)),
+            _ => None,
+        }
+    }
+}
+
+fn get_mime_type(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .as_deref()
+    {
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("webp") => "image/webp",
+        Some("ogg") => "audio/ogg",
+        Some("wav") => "audio/wav",
+        _ => "application/octet-stream",
+    }
+}
+
+fn respond(status: StatusCode) -> Response<Vec<u8>> {
+    Response::builder().status(status).body(vec![]).unwrap()
+}
+
+pub fn not_found() -> Response<Vec<u8>> {
+    respond(StatusCode::NOT_FOUND)
+}
+
+// only a single range is supported, which is all the media elements ask for
+fn parse_range(range: &str, size: u64) -> Option<(u64, u64)> {
+    let (start, end) = range.strip_prefix("bytes=")?.split_once('-')?;
+
+    let (start, end) = if start.is_empty() {
+        // the last n bytes
+        let n = end.parse::<u64>().ok()?.min(size);
+
+        (size - n, size.checked_sub(1)?)
+    } else {
+        let start = start.parse::<u64>().ok()?;
+        let end = if end.is_empty() {
+            size.checked_sub(1)?
+        } else {
+            end.parse::<u64>().ok()?.min(size.checked_sub(1)?)
+        };
+
+        (start, end)
+    };
+
+    (start <= end).then_some((start, end))
+}
+
+// streams the file, or the requested part of it
+pub fn serve(path: &Path, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let Ok(mut file) = File::open(path) else {
+        return not_found();
+    };
+    let Ok(size) = file.metadata().map(|m| m.len()) else {
+        return not_found();
+    };
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, get_mime_type(path))
+        .header(header::ACCEPT_RANGES, "bytes");
+
+    let Some(range) = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|r| r.to_str().ok())
+    else {
+        let mut body = Vec::with_capacity(size as usize);
+
+        return match file.read_to_end(&mut body) {
+            Ok(_) => builder
+                .status(StatusCode::OK)
+                .header(header::CONTENT_LENGTH, body.len())
+                .body(body)
+                .unwrap(),
+            Err(_) => respond(StatusCode::INTERNAL_SERVER_ERROR),
+        };
+    };
+
+    let Some((start, end)) = parse_range(range, size) else {
+        return Response::builder()
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", size))
+            .body(vec![])
+            .unwrap();
+    };
+
+    let mut body = vec![0; (end - start + 1) as usize];
+
+    match file
+        .seek(SeekFrom::Start(start))
+        .and_then(|_| file.read_exact(&mut body))
+    {
+        Ok(_) => builder
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(header::CONTENT_LENGTH, body.len())
+            .header(
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, size),
+            )
+            .body(body)
+            .unwrap(),
+        Err(_) => respond(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ranges() {
+        for (range, size, expected) in [
+            ("bytes=0-99", 1000, Some((0, 99))),
+            ("bytes=0-", 1000, Some((0, 999))),
+            ("bytes=500-", 1000, Some((500, 999))),
+            ("bytes=999-", 1000, Some((999, 999))),
+            ("bytes=900-2000", 1000, Some((900, 999))),
+            ("bytes=5-5", 1000, Some((5, 5))),
+            // suffix ranges
+            ("bytes=-100", 1000, Some((900, 999))),
+            ("bytes=-1", 1000, Some((999, 999))),
+            ("bytes=-2000", 1000, Some((0, 999))),
+            ("bytes=-0", 1000, None),
+            // past the end of the file
+            ("bytes=1000-", 1000, None),
+            ("bytes=1000-1200", 1000, None),
+            // empty files can't satisfy any range
+            ("bytes=0-", 0, None),
+            ("bytes=-10", 0, None),
+            // malformed
+            ("bytes=10-5", 1000, None),
+            ("bytes=-", 1000, None),
+            ("bytes=a-b", 1000, None),
+            ("bytes=0-1,5-9", 1000, None),
+            ("items=0-10", 1000, None),
+            ("bytes=10", 1000, None),
+            ("", 1000, None),
+        ] {
+            assert_eq!(parse_range(range, size), expected, "{} of {}", range, size);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{paginated_result::PaginatedResult, preset::Preset, product::ProductKey};
+use ordered_hash_map::OrderedHashMap;
+use std::collections::{HashMap, HashSet};
+
+// the filters of the preset list and the facet trees, empty filters let everything through
+#[derive(Default)]
+pub struct PresetQuery<'a> {
+    pub vendors: &'a [usize],
+    pub products: &'a [ProductKey],
+    pub categories: &'a [usize],
+    pub modes: &'a [usize],
+    pub banks: &'a [usize],
+    pub sources: &'a [usize],
+    pub include_broken: bool,
+    // lowercase, matched against name and comment, see SearchIndex
+    pub text: &'a str,
+}
+
+impl PresetQuery<'_> {
+    // everything but the text, which needs the search index
+    pub fn matches(&self, p: &Preset) -> bool {
+        (self.vendors.is_empty() || self.vendors.contains(&p.vendor_id))
+            && (self.products.is_empty() || self.products.contains(&p.product_id))
+            && (self.categories.is_empty() || self.categories.iter().any(|c| p.has_category(*c)))
+            && (self.modes.is_empty() || self.modes.iter().any(|m| p.has_mode(*m)))
+            && (self.banks.is_empty() || self.banks.contains(&p.bank))
+            && (self.sources.is_empty() || self.sources.contains(&p.source))
+            && (self.include_broken || !p.status.is_broken())
+    }
+
+    // whether any of the given presets matches, facets only list entries which leave something to show
+    pub fn matches_any(
+        &self,
+        presets: &OrderedHashMap<usize, Preset>,
+        ids: &HashSet<usize>,
+    ) -> bool {
+        ids.iter()
+            .filter_map(|id| presets.get(id))
+            .any(|p| self.matches(p))
+    }
+
+    // ids of all matching presets, in library order
+    pub fn filter(
+        &self,
+        presets: &OrderedHashMap<usize, Preset>,
+        index: &SearchIndex,
+    ) -> Vec<usize> {
+        presets
+            .values()
+            .filter(|p| self.matches(p) && index.contains(p.id, self.text))
+            .map(|p| p.id)
+            .collect::<Vec<_>>()
+    }
+}
+
+// lowercase name and comment of every preset, built once per library load
+#[derive(Default)]
+pub struct SearchIndex {
+    texts: HashMap<usize, (String, String)>,
+}
+
+impl SearchIndex {
+    pub fn new<'a>(presets: impl Iterator<Item = &'a Preset>) -> Self {
+        Self {
+            texts: presets
+                .map(|p| (p.id, (p.name.to_lowercase(), p.comment.to_lowercase())))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    pub fn contains(&self, id: usize, text: &str) -> bool {
+        text.is_empty()
+            || self
+                .texts
+                .get(&id)
+                .is_some_and(|(name, comment)| name.contains(text) || comment.contains(text))
+    }
+}
+
+// only the presets on the requested page get copied
+pub fn paginate(
+    presets: &OrderedHashMap<usize, Preset>,
+    ids: &[usize],
+    offset: usize,
+    limit: usize,
+) -> PaginatedResult<Preset> {
+    let results = ids
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .filter_map(|id| presets.get(id).cloned())
+        .collect::<Vec<_>>();
+
+    PaginatedResult {
+        start: offset + 1,
+        end: offset + results.len(),
+        total: ids.len(),
+        results,
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{
+    category::{Bank, Category, Mode},
+    preset::Preset,
+    product::{Product, ProductKey},
+};
+use multi_key_map::MultiKeyMap;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+#[derive(Serialize)]
+pub struct Count {
+    pub name: String,
+    pub presets: usize,
+}
+
+#[derive(Serialize)]
+pub struct PreviewCoverage {
+    pub product: String,
+    pub presets: usize,
+    pub previews: usize,
+}
+
+#[derive(Serialize)]
+pub struct MissingContentDir {
+    pub product: String,
+    pub content_dir: String,
+}
+
+#[derive(Default, Serialize)]
+pub struct LibraryReport {
+    pub presets: usize,
+    pub presets_per_vendor: Vec<Count>,
+    pub presets_per_product: Vec<Count>,
+    pub presets_per_category: Vec<Count>,
+    pub presets_per_mode: Vec<Count>,
+    pub presets_without_category: Vec<usize>,
+    pub empty_banks: Vec<String>,
+    pub missing_content_dirs: Vec<MissingContentDir>,
+    pub missing_files: Vec<PathBuf>,
+    pub preview_coverage: Vec<PreviewCoverage>,
+}
+
+fn sorted_counts(entries: impl Iterator<Item = (String, usize)>) -> Vec<Count> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+
+    // different entries may share a name, e.g. products with the same alias
+    for (name, presets) in entries {
+        *counts.entry(name).or_default() += presets;
+    }
+
+    let mut counts: Vec<Count> = counts
+        .into_iter()
+        .map(|(name, presets)| Count { name, presets })
+        .collect::<Vec<_>>();
+
+    counts.sort_by(|a, b| natord::compare_ignore_case(&a.name, &b.name));
+
+    counts
+}
+
+impl LibraryReport {
+    // everything that can be computed from the loaded model alone
+    pub fn new<'a>(
+        presets: impl Iterator<Item = &'a Preset>,
+        products: impl Iterator<Item = &'a Product>,
+        categories: impl Iterator<Item = &'a Category>,
+        modes: impl Iterator<Item = &'a Mode>,
+        banks: impl Iterator<Item = &'a Bank>,
+    ) -> Self {
+        let mut report = Self::default();
+        let mut vendors: Vec<(String, usize)> = vec![];
+
+        for preset in presets {
+            report.presets += 1;
+            vendors.push((preset.vendor.to_string(), 1));
+
+            if preset.categories.is_empty() {
+                report.presets_without_category.push(preset.id);
+            }
+        }
+
+        report.presets_per_vendor = sorted_counts(vendors.into_iter());
+        report.presets_per_product =
+            sorted_counts(products.map(|p| (p.name.clone(), p.presets.len())));
+        report.presets_per_category =
+            sorted_counts(categories.map(|c| (c.get_name(), c.presets.len())));
+        report.presets_per_mode = sorted_counts(modes.map(|m| (m.name.clone(), m.presets.len())));
+        report.empty_banks = banks
+            .filter(|b| b.presets.is_empty())
+            .map(|b| b.get_name())
+            .collect::<Vec<_>>();
+
+        report
+    }
+
+    // the parts of the report which need to touch the file system
+    pub fn check_files<'a>(
+        &mut self,
+        presets: impl Iterator<Item = &'a Preset>,
+        products: &MultiKeyMap<ProductKey, Product>,
+    ) {
+        let mut sorted: Vec<&Product> = products.values().collect::<Vec<_>>();
+
+        sorted.sort();
+
+        self.missing_content_dirs = sorted
+            .iter()
+            .filter(|p| !p.content_dir.is_empty() && !Path::new(&p.content_dir).exists())
+            .map(|p| MissingContentDir {
+                product: p.name.clone(),
+                content_dir: p.content_dir.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut coverage: HashMap<usize, usize> = HashMap::new();
+
+        for preset in presets {
+            if !preset.file_name.exists() {
+                self.missing_files.push(preset.file_name.clone());
+                continue;
+            }
+
+            if let Some(product) = products.get(&preset.product_id)
+                && preset.get_preview_path(product).is_some()
+            {
+                *coverage.entry(product.id).or_default() += 1;
+            }
+        }
+
+        self.preview_coverage = sorted
+            .iter()
+            .map(|p| PreviewCoverage {
+                product: p.name.clone(),
+                presets: p.presets.len(),
+                previews: coverage.get(&p.id).copied().unwrap_or(0),
+            })
+            .collect::<Vec<_>>();
+    }
+}
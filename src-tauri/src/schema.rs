@@ -0,0 +1,105 @@
+use crate::db3::Db3Error;
+use rusqlite::Connection;
+use std::collections::HashSet;
+
+// every table and column the loader reads, regardless of the schema version
+const TABLES: [(&str, &[&str]); 7] = [
+    (
+        "k_sound_info",
+        &[
+            "id",
+            "name",
+            "vendor",
+            "comment",
+            "content_path_id",
+            "file_name",
+            "bank_chain_id",
+        ],
+    ),
+    ("k_bank_chain", &["id", "entry1", "entry2", "entry3"]),
+    ("k_content_path", &["id", "path", "alias", "upid"]),
+    (
+        "k_category",
+        &["id", "category", "subcategory", "subsubcategory"],
+    ),
+    ("k_sound_info_category", &["sound_info_id", "category_id"]),
+    ("k_mode", &["id", "name"]),
+    ("k_sound_info_mode", &["sound_info_id", "mode_id"]),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SchemaVersion {
+    // older databases don't know about product ids yet
+    WithoutUpid,
+    Current,
+}
+
+impl SchemaVersion {
+    // columns a version doesn't have, everything else in TABLES is required
+    pub fn get_absent_columns(&self) -> &'static [&'static str] {
+        match self {
+            SchemaVersion::WithoutUpid => &["k_content_path.upid"],
+            SchemaVersion::Current => &[],
+        }
+    }
+
+    // every query goes through here, columns the version doesn't have yet are read as empty strings
+    pub fn select(&self, table: &str, columns: &[&str]) -> String {
+        let columns = columns
+            .iter()
+            .map(|c| {
+                if self
+                    .get_absent_columns()
+                    .contains(&format!("{}.{}", table, c).as_str())
+                {
+                    format!("'' AS {}", c)
+                } else {
+                    c.to_string()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        format!("SELECT {} FROM {}", columns.join(", "), table)
+    }
+}
+
+fn get_columns(conn: &Connection, table: &str) -> Result<HashSet<String>, Db3Error> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+
+    let columns = stmt
+        .query_map([], |row| row.get::<&str, String>("name"))?
+        .filter_map(|c| c.ok())
+        .collect::<HashSet<_>>();
+
+    Ok(columns)
+}
+
+// finds the newest schema version the database matches, or reports everything that's missing
+pub fn detect(conn: &Connection) -> Result<SchemaVersion, Db3Error> {
+    let mut missing: Vec<String> = vec![];
+
+    for (table, columns) in TABLES {
+        let found = get_columns(conn, table)?;
+
+        if found.is_empty() {
+            missing.push(table.to_string());
+            continue;
+        }
+
+        missing.extend(
+            columns
+                .iter()
+                .filter(|c| !found.contains(**c))
+                .map(|c| format!("{}.{}", table, c)),
+        );
+    }
+
+    [SchemaVersion::Current, SchemaVersion::WithoutUpid]
+        .into_iter()
+        .find(|v| {
+            missing
+                .iter()
+                .all(|m| v.get_absent_columns().contains(&m.as_str()))
+        })
+        .ok_or(Db3Error::UnsupportedSchema(missing))
+}
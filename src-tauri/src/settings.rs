@@ -0,0 +1,42 @@
+use crate::loudness::NormalizationSettings;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Settings {
+    // folders of NKS files, browsed alongside the databases
+    #[serde(default)]
+    pub library_folders: Vec<PathBuf>,
+    // komplete.db3 files of other machines, loaded in addition to the local one
+    #[serde(default)]
+    pub extra_databases: Vec<PathBuf>,
+    // spelling variant -> vendor name it should be merged into
+    #[serde(default)]
+    pub vendor_aliases: HashMap<String, String>,
+    // folders with installed_products descriptors, the default NI location is used if empty
+    #[serde(default)]
+    pub installed_products_folders: Vec<PathBuf>,
+    #[serde(default)]
+    pub normalization: NormalizationSettings,
+}
+
+impl Settings {
+    pub fn load(config_dir: &Path) -> Self {
+        File::open(config_dir.join("settings.json"))
+            .ok()
+            .and_then(|f| serde_json::from_reader(f).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, config_dir: &Path) {
+        if fs::create_dir_all(config_dir).is_ok()
+            && let Ok(file) = File::create(config_dir.join("settings.json"))
+        {
+            let _ = serde_json::to_writer_pretty(file, self);
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use std::sync::{Arc, RwLock};
+
+// holds a value that is only ever replaced as a whole
+// readers take the current version and work on it without holding any lock,
+// the lock itself is only held for as long as it takes to copy or swap the pointer
+pub struct Snapshot<T> {
+    current: RwLock<Arc<T>>,
+}
+
+impl<T> Snapshot<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(value)),
+        }
+    }
+
+    pub fn load(&self) -> Arc<T> {
+        self.current.read().unwrap().clone()
+    }
+
+    // the condition is checked while holding the lock, so no other store can slip in between
+    pub fn store_if(&self, value: T, condition: impl FnOnce() -> bool) -> bool {
+        let mut current = self.current.write().unwrap();
+
+        if !condition() {
+            return false;
+        }
+
+        *current = Arc::new(value);
+        true
+    }
+
+    // replaces the value only if it is still the one the replacement was built from
+    pub fn replace(&self, from: &Arc<T>, value: T) -> bool {
+        let mut current = self.current.write().unwrap();
+
+        if !Arc::ptr_eq(&current, from) {
+            return false;
+        }
+
+        *current = Arc::new(value);
+        true
+    }
+}
@@ -0,0 +1,281 @@
+use crate::{
+    category::{Bank, Category, Mode},
+    library::Library,
+    load_report::LoadReport,
+    preset::Preset,
+    product::{Product, ProductKey},
+    progress::{LoadingPhase, LoadingProgress, ProgressCallback},
+};
+use multi_key_map::MultiKeyMap;
+use ordered_hash_map::OrderedHashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+// ids of every source are moved into their own range, so rows of different databases never collide
+const ID_BITS: usize = 40;
+
+pub trait LibrarySource: Send {
+    fn get_name(&self) -> String;
+
+    // None if the source isn't available, e.g. the database doesn't exist
+    fn load(&self, progress: ProgressCallback) -> Result<Option<Library>, String>;
+
+    // identifies the current state of the source, None if changes can't be detected cheaply
+    fn get_stamp(&self) -> Option<SourceStamp>;
+}
+
+// if the stamp of a source didn't change, a cached copy of its library is still up to date
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SourceStamp {
+    pub name: String,
+    pub size: u64,
+    pub modified: u64,
+    pub schema: i64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SourceInfo {
+    pub id: usize,
+    pub name: String,
+    pub presets: usize,
+    // set if the source exists, but couldn't be loaded
+    pub error: Option<String>,
+    // the moment the loaded data reflects, if the source was copied before loading
+    pub snapshot_time: Option<u64>,
+}
+
+fn namespace_id(source: usize, id: usize) -> usize {
+    // 0 means "none" and has to stay that way
+    if id == 0 { 0 } else { (source << ID_BITS) | id }
+}
+
+// maps ids of a single source to the merged ids, entries with the same key share one id
+struct Merger<K, T> {
+    keys: HashMap<K, usize>,
+    entries: HashMap<usize, T>,
+}
+
+impl<K: Eq + std::hash::Hash, T> Merger<K, T> {
+    fn new() -> Self {
+        Self {
+            keys: HashMap::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    fn merge(&mut self, key: K, id: usize, entry: impl FnOnce(usize) -> T) -> &mut T {
+        let id = *self.keys.entry(key).or_insert(id);
+
+        self.entries.entry(id).or_insert_with(|| entry(id))
+    }
+
+    fn into_sorted(self) -> Vec<T>
+    where
+        T: Ord,
+    {
+        let mut entries: Vec<T> = self.entries.into_values().collect::<Vec<_>>();
+
+        entries.sort();
+
+        entries
+    }
+}
+
+// loads all available sources and combines them into a single library
+pub fn load_sources(
+    sources: &[Box<dyn LibrarySource>],
+    progress: &dyn Fn(LoadingProgress),
+) -> (Library, Vec<SourceInfo>, Vec<LoadReport>) {
+    let mut infos: Vec<SourceInfo> = vec![];
+    let mut reports: Vec<LoadReport> = vec![];
+    let mut banks: Merger<(String, String, String), Bank> = Merger::new();
+    let mut categories: Merger<(String, String, String), Category> = Merger::new();
+    let mut modes: Merger<String, Mode> = Merger::new();
+    let mut products: MultiKeyMap<ProductKey, Product> = MultiKeyMap::new();
+    let mut presets: Vec<Preset> = vec![];
+
+    for (source, s) in sources.iter().enumerate() {
+        let report = |phase: LoadingPhase, processed: usize, total: usize| {
+            progress(LoadingProgress {
+                source,
+                sources: sources.len(),
+                phase,
+                processed,
+                total,
+            })
+        };
+
+        let library = match s.load(&report) {
+            Ok(Some(library)) => library,
+            Ok(None) => continue,
+            Err(e) => {
+                infos.push(SourceInfo {
+                    id: source,
+                    name: s.get_name(),
+                    presets: 0,
+                    error: Some(e),
+                    snapshot_time: None,
+                });
+                continue;
+            }
+        };
+
+        infos.push(SourceInfo {
+            id: source,
+            name: s.get_name(),
+            presets: library.presets.len(),
+            error: None,
+            snapshot_time: library.snapshot_time,
+        });
+
+        if !library.warnings.is_empty() {
+            reports.push(LoadReport {
+                source,
+                name: s.get_name(),
+                warnings: library.warnings.clone(),
+            });
+        }
+
+        let mut bank_ids: HashMap<usize, usize> = HashMap::new();
+
+        for b in library.banks.values() {
+            let merged = banks.merge(
+                (b.entry1.clone(), b.entry2.clone(), b.entry3.clone()),
+                namespace_id(source, b.id),
+                |id| Bank {
+                    id,
+                    presets: HashSet::new(),
+                    ..b.clone()
+                },
+            );
+
+            merged
+                .presets
+                .extend(b.presets.iter().map(|p| namespace_id(source, *p)));
+            bank_ids.insert(b.id, merged.id);
+        }
+
+        let mut category_ids: HashMap<usize, usize> = HashMap::new();
+
+        for c in library.categories.values() {
+            let merged = categories.merge(
+                (
+                    c.name.clone(),
+                    c.subcategory.clone(),
+                    c.subsubcategory.clone(),
+                ),
+                namespace_id(source, c.id),
+                |id| Category {
+                    id,
+                    presets: HashSet::new(),
+                    ..c.clone()
+                },
+            );
+
+            merged
+                .presets
+                .extend(c.presets.iter().map(|p| namespace_id(source, *p)));
+            category_ids.insert(c.id, merged.id);
+        }
+
+        let mut mode_ids: HashMap<usize, usize> = HashMap::new();
+
+        for m in library.modes.values() {
+            let merged = modes.merge(m.name.clone(), namespace_id(source, m.id), |id| Mode {
+                id,
+                presets: HashSet::new(),
+                ..m.clone()
+            });
+
+            merged
+                .presets
+                .extend(m.presets.iter().map(|p| namespace_id(source, *p)));
+            mode_ids.insert(m.id, merged.id);
+        }
+
+        for p in library.products.values() {
+            let id = namespace_id(source, p.id);
+            let key = ProductKey::Id(id);
+
+            if products.contains_key(&key) {
+                continue;
+            }
+
+            // the same product may be installed on several machines, the first one keeps the upid
+            let keys: Vec<ProductKey> = match p.upid.as_str() {
+                "" => vec![key],
+                upid if products.contains_key(&ProductKey::Upid(upid.to_string())) => vec![key],
+                upid => vec![key, ProductKey::Upid(upid.to_string())],
+            };
+
+            products.insert_many(
+                keys,
+                Product {
+                    id,
+                    presets: p
+                        .presets
+                        .iter()
+                        .map(|pr| namespace_id(source, *pr))
+                        .collect::<HashSet<_>>(),
+                    ..p.clone()
+                },
+            );
+        }
+
+        for p in library.presets.values() {
+            let product = match &p.product_id {
+                ProductKey::Id(id) => ProductKey::Id(namespace_id(source, *id)),
+                ProductKey::Upid(upid) => ProductKey::Upid(upid.clone()),
+            };
+
+            presets.push(Preset {
+                id: namespace_id(source, p.id),
+                product_id: product,
+                categories: p
+                    .categories
+                    .iter()
+                    .filter_map(|c| category_ids.get(c).copied())
+                    .collect::<HashSet<_>>(),
+                modes: p
+                    .modes
+                    .iter()
+                    .filter_map(|m| mode_ids.get(m).copied())
+                    .collect::<HashSet<_>>(),
+                bank: bank_ids.get(&p.bank).copied().unwrap_or(0),
+                source,
+                ..p.clone()
+            });
+        }
+    }
+
+    let mut library = Library {
+        banks: OrderedHashMap::new(),
+        categories: OrderedHashMap::new(),
+        modes: OrderedHashMap::new(),
+        products,
+        presets: OrderedHashMap::new(),
+        // the warnings of every source end up in its load report
+        warnings: vec![],
+        snapshot_time: None,
+    };
+
+    banks.into_sorted().into_iter().for_each(|b| {
+        library.banks.insert(b.id, b);
+    });
+
+    categories.into_sorted().into_iter().for_each(|c| {
+        library.categories.insert(c.id, c);
+    });
+
+    modes.into_sorted().into_iter().for_each(|m| {
+        library.modes.insert(m.id, m);
+    });
+
+    presets.sort();
+
+    presets.into_iter().for_each(|p| {
+        library.presets.insert(p.id, p);
+    });
+
+    (library, infos, reports)
+}
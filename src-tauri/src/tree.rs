@@ -0,0 +1,102 @@
+use serde::Serialize;
+use std::collections::HashSet;
+
+#[derive(Clone, Serialize)]
+pub struct TreeNode {
+    pub name: String,
+    // names from the root down to this node
+    pub path: Vec<String>,
+    // ids of all entries within this branch, selecting the node filters by all of them
+    pub ids: Vec<usize>,
+    // presets within this branch which match the current filters
+    pub presets: usize,
+    pub children: Vec<TreeNode>,
+}
+
+#[derive(Default)]
+struct Branch {
+    name: String,
+    ids: Vec<usize>,
+    presets: HashSet<usize>,
+    children: Vec<Branch>,
+}
+
+impl Branch {
+    fn into_node(self, parent: &[String]) -> Option<TreeNode> {
+        if self.presets.is_empty() {
+            return None;
+        }
+
+        let mut path = parent.to_vec();
+
+        path.push(self.name.clone());
+
+        Some(TreeNode {
+            children: self
+                .children
+                .into_iter()
+                .filter_map(|c| c.into_node(&path))
+                .collect::<Vec<_>>(),
+            name: self.name,
+            path,
+            ids: self.ids,
+            presets: self.presets.len(),
+        })
+    }
+}
+
+// builds a tree from entries with a flattened path, like types or bank chains
+// empty path elements are skipped, so "Bass" and "Bass / Synth Bass" end up in the same branch
+pub fn build<'a>(
+    entries: impl Iterator<Item = (Vec<String>, usize, &'a HashSet<usize>)>,
+    matches: impl Fn(usize) -> bool,
+) -> Vec<TreeNode> {
+    let mut root = Branch::default();
+
+    for (path, id, presets) in entries {
+        let presets = presets
+            .iter()
+            .copied()
+            .filter(|p| matches(*p))
+            .collect::<Vec<_>>();
+        let mut branch = &mut root;
+
+        for name in path.into_iter().filter(|n| !n.is_empty()) {
+            let index = match branch.children.iter().position(|c| c.name == name) {
+                Some(index) => index,
+                None => {
+                    branch.children.push(Branch {
+                        name,
+                        ..Branch::default()
+                    });
+                    branch.children.len() - 1
+                }
+            };
+
+            branch = &mut branch.children[index];
+            branch.ids.push(id);
+            branch.presets.extend(presets.iter().copied());
+        }
+    }
+
+    root.children
+        .into_iter()
+        .filter_map(|c| c.into_node(&[]))
+        .collect::<Vec<_>>()
+}
+
+// the children of the node at the given path, without their own children
+pub fn get_children(mut nodes: Vec<TreeNode>, path: &[String]) -> Vec<TreeNode> {
+    for name in path {
+        match nodes.into_iter().find(|n| &n.name == name) {
+            Some(node) => nodes = node.children,
+            None => return vec![],
+        }
+    }
+
+    for node in nodes.iter_mut() {
+        node.children.clear();
+    }
+
+    nodes
+}
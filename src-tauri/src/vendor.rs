@@ -0,0 +1,83 @@
+use crate::fnv;
+use serde::Serialize;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+// ids have to survive the trip through JavaScript numbers
+const ID_MASK: u64 = (1 << 52) - 1;
+
+#[derive(Clone, Serialize)]
+pub struct Vendor {
+    pub id: usize,
+    // presets and products of the vendor share this very string
+    pub name: Arc<str>,
+    #[serde(skip)]
+    pub presets: HashSet<usize>,
+    #[serde(skip)]
+    pub products: HashSet<usize>,
+}
+
+impl Vendor {
+    // derived from the name, so the id stays the same across reloads
+    pub fn get_id(name: &str) -> usize {
+        let hash = fnv::hash(name.to_lowercase().as_bytes());
+
+        // 0 is reserved for pseudo entries
+        ((hash & ID_MASK) as usize).max(1)
+    }
+}
+
+impl Ord for Vendor {
+    fn cmp(&self, other: &Self) -> Ordering {
+        natord::compare_ignore_case(&self.name, &other.name)
+    }
+}
+
+impl PartialOrd for Vendor {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Eq for Vendor {}
+
+impl PartialEq for Vendor {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Hash for Vendor {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+// maps spelling variants to the name they should be shown as
+pub struct VendorAliases {
+    aliases: HashMap<String, String>,
+}
+
+impl VendorAliases {
+    pub fn new(aliases: &HashMap<String, String>) -> Self {
+        Self {
+            aliases: aliases
+                .iter()
+                .map(|(variant, name)| (variant.trim().to_lowercase(), name.trim().to_string()))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    pub fn resolve(&self, name: &str) -> String {
+        let name = name.trim();
+
+        self.aliases
+            .get(&name.to_lowercase())
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+}
@@ -0,0 +1,105 @@
+use crate::fnv;
+use rodio::{Decoder, Source};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct Waveform {
+    // length of the preview in seconds
+    pub duration: f32,
+    // (min, max) sample value per bucket
+    pub peaks: Vec<(f32, f32)>,
+}
+
+// the cache keeps a single file per preview, which gets replaced once the preview or the resolution changes
+#[derive(Serialize, Deserialize)]
+struct CachedWaveform {
+    path: PathBuf,
+    // modification time of the preview, since the epoch
+    modified: Duration,
+    resolution: usize,
+    waveform: Waveform,
+}
+
+impl Waveform {
+    pub fn compute<S: Source>(source: S, resolution: usize) -> Self {
+        let channels = source.channels() as usize;
+        let sample_rate = source.sample_rate() as usize;
+        let samples: Vec<f32> = source.collect::<Vec<_>>();
+
+        let duration = if channels > 0 && sample_rate > 0 {
+            samples.len() as f32 / (channels * sample_rate) as f32
+        } else {
+            0.0
+        };
+
+        if samples.is_empty() || resolution == 0 {
+            return Self {
+                duration,
+                peaks: vec![],
+            };
+        }
+
+        let bucket_size = samples.len().div_ceil(resolution);
+
+        Self {
+            duration,
+            peaks: samples
+                .chunks(bucket_size)
+                .map(|bucket| {
+                    bucket.iter().fold((f32::MAX, f32::MIN), |(min, max), s| {
+                        (min.min(*s), max.max(*s))
+                    })
+                })
+                .collect::<Vec<_>>(),
+        }
+    }
+
+    // computes the waveform of the given preview file or reads it from the cache if possible
+    pub fn load(path: &Path, resolution: usize, cache_dir: &Path) -> Option<Self> {
+        let cache_path = cache_dir.join("waveforms").join(format!(
+            "{:016x}.json",
+            fnv::hash(path.to_string_lossy().as_bytes())
+        ));
+        let modified = fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok());
+
+        if let Some(cached) = File::open(&cache_path)
+            .ok()
+            .and_then(|f| serde_json::from_reader::<_, CachedWaveform>(f).ok())
+            && cached.path == path
+            && Some(cached.modified) == modified
+            && cached.resolution == resolution
+        {
+            return Some(cached.waveform);
+        }
+
+        let waveform = Self::compute(Decoder::try_from(File::open(path).ok()?).ok()?, resolution);
+
+        // without a modification time the cached file could never be trusted
+        let Some(modified) = modified else {
+            return Some(waveform);
+        };
+
+        let cached = CachedWaveform {
+            path: path.to_path_buf(),
+            modified,
+            resolution,
+            waveform,
+        };
+
+        if fs::create_dir_all(cache_dir.join("waveforms")).is_ok()
+            && let Ok(file) = File::create(&cache_path)
+        {
+            let _ = serde_json::to_writer(file, &cached);
+        }
+
+        Some(cached.waveform)
+    }
+}
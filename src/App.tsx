@@ -9,7 +9,13 @@ function App() {
 
     useEffect(() => {
         ;(async () => {
-            if (!(await invoke("db_found"))) navigate("/db-not-found")
+            if (
+                !(await invoke("db_found")) &&
+                ((await invoke("get_library_folders")) as string[]).length ===
+                    0 &&
+                ((await invoke("get_extra_databases")) as string[]).length === 0
+            )
+                navigate("/db-not-found")
         })()
     }, [navigate])
 
@@ -1,9 +1,12 @@
 import { invoke } from "@tauri-apps/api/core"
 import { exit } from "@tauri-apps/plugin-process"
 import { useEffect, useState } from "react"
+import { useNavigate } from "react-router"
 
 function DbNotFound() {
     let [dbPath, setDbPath] = useState("")
+    let [folder, setFolder] = useState("")
+    const navigate = useNavigate()
 
     useEffect(() => {
         ;(async () => {
@@ -23,6 +26,27 @@ function DbNotFound() {
                 This application is looking in the following path to find the
                 database file: {dbPath}
             </p>
+            <p>
+                Alternatively, you can browse a folder containing NKS preset
+                files instead.
+            </p>
+            <label>
+                Preset folder:{" "}
+                <input
+                    type="text"
+                    value={folder}
+                    onChange={(e) => setFolder(e.target.value)}
+                />
+            </label>
+            <button
+                disabled={folder === ""}
+                onClick={async () => {
+                    await invoke("set_library_folders", { folders: [folder] })
+                    navigate("/")
+                }}
+            >
+                Use folder
+            </button>
             <button onClick={async () => exit(0)}>Exit</button>
         </>
     )
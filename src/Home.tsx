@@ -1,13 +1,14 @@
 import { invoke } from "@tauri-apps/api/core"
 import { writeText } from "@tauri-apps/plugin-clipboard-manager"
 import natsort from "natsort"
-import { useEffect, useMemo, useState } from "react"
+import { Fragment, useEffect, useMemo, useState } from "react"
 import Button from "react-bootstrap/Button"
 import Form from "react-bootstrap/Form"
 import Modal from "react-bootstrap/Modal"
 import { AsyncPaginate as Select } from "react-select-async-paginate"
 import slugify from "slugify"
-import { joinString } from "./utils"
+import TreeList, { TreeNode } from "./TreeList"
+import { getResourceUrl, joinString } from "./utils"
 
 const PAGE_SIZE = 500
 
@@ -21,12 +22,40 @@ interface Preset {
     categories: number[]
     modes: number[]
     bank: number
+    status: "unknown" | "ok" | "missing_file" | "missing_content_dir"
+    source: number
+}
+
+interface Vendor {
+    id: number
+    name: string
+}
+
+interface ProductInfo {
+    name: string
+    version: string | null
+    install_date: number | null
+    product_type: string | null
+    artwork: string | null
+    logo: string | null
 }
 
 interface Product {
     name: string
     vendor: string
+    vendor_id: number
     id: number
+    info: ProductInfo | null
+}
+
+interface ProductDetails extends Product {
+    content_dir: string
+    upid: string
+    presets: number
+}
+
+function getProductName(p: Product): string {
+    return p.info?.name || p.name || "(unnamed product)"
 }
 
 interface Category {
@@ -48,6 +77,17 @@ interface Bank {
     entry3: string
 }
 
+interface Knob {
+    name: string
+    section: string
+}
+
+interface ParameterPage {
+    name: string
+    sections: string[]
+    knobs: Knob[]
+}
+
 interface PaginatedResult<T> {
     results: T[]
     total: number
@@ -55,20 +95,66 @@ interface PaginatedResult<T> {
     end: number
 }
 
+interface LoadingProgress {
+    source: number
+    sources: number
+    phase: string
+    processed: number
+    total: number
+}
+
+const LOADING_PHASES: { [phase: string]: string } = {
+    banks: "banks",
+    content_paths: "products",
+    presets: "presets",
+    categories: "types",
+    modes: "characteristics",
+    files: "preset files",
+}
+
+interface SourceInfo {
+    id: number
+    name: string
+    presets: number
+    error: string | null
+    snapshot_time: number | null
+}
+
+interface LoadWarning {
+    kind: string
+    missing?: string[]
+}
+
+interface LoadReport {
+    source: number
+    name: string
+    warnings: LoadWarning[]
+}
+
 interface PresetOption extends Preset {
     label: string
 }
 
 function Home() {
     const [loading, setLoading] = useState(true)
-    const [vendors, setVendors] = useState<string[]>([])
-    const [selectedVendors, setSelectedVendors] = useState<string[]>([])
+    const [refreshing, setRefreshing] = useState(true)
+    const [progress, setProgress] = useState<LoadingProgress | null>(null)
+    const [sources, setSources] = useState<SourceInfo[]>([])
+    const [selectedSources, setSelectedSources] = useState<number[]>([])
+    const [temporarilySelectedSources, setTemporarilySelectedSources] =
+        useState<number[]>([])
+    const [loadReports, setLoadReports] = useState<LoadReport[]>([])
+    const [vendors, setVendors] = useState<Map<number, Vendor>>(new Map())
+    const [selectedVendors, setSelectedVendors] = useState<number[]>([])
     const [temporarilySelectedVendors, setTemporarilySelectedVendors] =
-        useState<string[]>([])
+        useState<number[]>([])
     const [products, setProducts] = useState<Map<number, Product>>(new Map())
     const [selectedProducts, setSelectedProducts] = useState<number[]>([])
     const [temporarilySelectedProducts, setTemporarilySelectedProducts] =
         useState<number[]>([])
+    const [productDetails, setProductDetails] = useState<ProductDetails | null>(
+        null,
+    )
     const sorter = useMemo(natsort, [])
     const [selectedPreset, setSelectedPreset] = useState<
         PresetOption | undefined
@@ -76,16 +162,19 @@ function Home() {
     const [categories, setCategories] = useState<Map<number, Category>>(
         new Map(),
     )
+    const [categoryTree, setCategoryTree] = useState<TreeNode[]>([])
     const [selectedCategories, setSelectedCategories] = useState<number[]>([])
     const [temporarilySelectedCategories, setTemporarilySelectedCategories] =
         useState<number[]>([])
     const [banks, setBanks] = useState<Map<number, Bank>>(new Map())
+    const [bankTree, setBankTree] = useState<TreeNode[]>([])
     const [selectedBanks, setSelectedBanks] = useState<number[]>([])
     const [temporarilySelectedBanks, setTemporarilySelectedBanks] = useState<
         number[]
     >([])
     const [showProducts, setShowProducts] = useState(false)
     const [showVendors, setShowVendors] = useState(false)
+    const [showSources, setShowSources] = useState(false)
     const [showCategories, setShowCategories] = useState(false)
     const [showModes, setShowModes] = useState(false)
     const [showBanks, setShowBanks] = useState(false)
@@ -96,6 +185,8 @@ function Home() {
         number[]
     >([])
     const [bankFilter, setBankFilter] = useState("")
+    const [hideBroken, setHideBroken] = useState(false)
+    const [parameterPages, setParameterPages] = useState<ParameterPage[]>([])
 
     useEffect(() => {
         ;(async () => {
@@ -104,24 +195,47 @@ function Home() {
 
                 while (server_loading) {
                     server_loading = await invoke("is_loading")
-                    if (server_loading)
+                    if (server_loading) {
+                        setProgress(await invoke("get_loading_progress"))
                         await new Promise((r) => setTimeout(r, 100))
+                    }
                 }
                 setLoading(false)
             }
         })()
-    }, [loading, setLoading])
+    }, [loading, setLoading, setProgress])
+
+    useEffect(() => {
+        ;(async () => {
+            // a cached library may be shown while the real one is still loading
+            if (!loading && refreshing) {
+                let server_refreshing = true
+
+                while (server_refreshing) {
+                    server_refreshing = await invoke("is_refreshing")
+                    if (server_refreshing)
+                        await new Promise((r) => setTimeout(r, 500))
+                }
+                setRefreshing(false)
+            }
+        })()
+    }, [loading, refreshing, setRefreshing])
 
     useEffect(() => {
         ;(async () => {
             if (!loading) {
                 setVendors(
-                    await invoke("get_vendors", {
-                        products: selectedProducts,
-                        categories: selectedCategories,
-                        modes: selectedModes,
-                        banks: selectedBanks,
-                    }),
+                    new Map(
+                        (
+                            (await invoke("get_vendors", {
+                                products: selectedProducts,
+                                categories: selectedCategories,
+                                modes: selectedModes,
+                                banks: selectedBanks,
+                                sources: selectedSources,
+                            })) as Vendor[]
+                        ).map((v) => [v.id, v]),
+                    ),
                 )
                 setProducts(
                     new Map(
@@ -131,6 +245,7 @@ function Home() {
                                 categories: selectedCategories,
                                 modes: selectedModes,
                                 banks: selectedBanks,
+                                sources: selectedSources,
                             })) as Product[]
                         ).map((p) => [p.id, p]),
                     ),
@@ -143,10 +258,21 @@ function Home() {
                                 products: selectedProducts,
                                 modes: selectedModes,
                                 banks: selectedBanks,
+                                sources: selectedSources,
                             })) as Category[]
                         ).map((c) => [c.id, c]),
                     ),
                 )
+                setCategoryTree(
+                    await invoke("get_category_tree", {
+                        vendors: selectedVendors,
+                        products: selectedProducts,
+                        modes: selectedModes,
+                        banks: selectedBanks,
+                        includeBroken: !hideBroken,
+                        sources: selectedSources,
+                    }),
+                )
                 setModes(
                     new Map(
                         (
@@ -155,6 +281,7 @@ function Home() {
                                 products: selectedProducts,
                                 categories: selectedCategories,
                                 banks: selectedBanks,
+                                sources: selectedSources,
                             })) as Mode[]
                         ).map((m) => [m.id, m]),
                     ),
@@ -167,32 +294,202 @@ function Home() {
                                 products: selectedProducts,
                                 categories: selectedCategories,
                                 modes: selectedModes,
+                                sources: selectedSources,
                             })) as Bank[]
                         ).map((b) => [b.id, b]),
                     ),
                 )
+                setBankTree(
+                    await invoke("get_bank_tree", {
+                        vendors: selectedVendors,
+                        products: selectedProducts,
+                        categories: selectedCategories,
+                        modes: selectedModes,
+                        includeBroken: !hideBroken,
+                        sources: selectedSources,
+                    }),
+                )
             }
         })()
     }, [
+        hideBroken,
         loading,
+        refreshing,
         selectedBanks,
         selectedCategories,
         selectedModes,
         selectedProducts,
+        selectedSources,
         selectedVendors,
+        setBankTree,
         setBanks,
         setCategories,
+        setCategoryTree,
         setModes,
         setProducts,
         setVendors,
     ])
 
+    useEffect(() => {
+        ;(async () => {
+            if (!loading) {
+                setSources(await invoke("get_sources"))
+                setLoadReports(await invoke("get_load_report"))
+            }
+        })()
+    }, [loading, refreshing, setLoadReports, setSources])
+
+    useEffect(() => {
+        ;(async () => {
+            if (selectedPreset === undefined) setParameterPages([])
+            else
+                try {
+                    setParameterPages(
+                        await invoke("get_preset_parameter_pages", {
+                            preset: selectedPreset.id,
+                        }),
+                    )
+                } catch {
+                    setParameterPages([])
+                }
+        })()
+    }, [selectedPreset, setParameterPages])
+
+    useEffect(() => {
+        ;(async () => {
+            if (loading || selectedProducts.length !== 1) setProductDetails(null)
+            else
+                setProductDetails(
+                    await invoke("get_product_details", {
+                        product: selectedProducts[0],
+                    }),
+                )
+        })()
+    }, [loading, refreshing, selectedProducts, setProductDetails])
+
     return loading ? (
-        <p>Loading Komplete Kontrol data, please wait...</p>
+        <>
+            <p>Loading Komplete Kontrol data, please wait...</p>
+            {progress !== null ? (
+                <p role="status">
+                    Source {progress.source + 1} of {progress.sources}: loading{" "}
+                    {LOADING_PHASES[progress.phase]} ({progress.processed} of{" "}
+                    {progress.total})
+                </p>
+            ) : null}
+        </>
     ) : (
         <>
+            {sources
+                .filter((s) => s.error !== null)
+                .map((s) => (
+                    <p role="alert" key={s.id}>
+                        Unable to load {s.name}: {s.error}
+                    </p>
+                ))}
+            {loadReports.map((r) => {
+                const olderSchema = r.warnings.find(
+                    (w) => w.kind === "older_schema",
+                )
+                const skipped = r.warnings.filter(
+                    (w) => w.kind !== "older_schema",
+                ).length
+
+                return (
+                    <Fragment key={r.source}>
+                        {olderSchema && (
+                            <p role="status">
+                                {r.name} was written by an older version of
+                                Komplete Kontrol, missing{" "}
+                                {olderSchema.missing?.join(", ")}.
+                            </p>
+                        )}
+                        {skipped > 0 && (
+                            <p role="status">
+                                {skipped} inconsistent entries were skipped
+                                while loading {r.name}.
+                            </p>
+                        )}
+                    </Fragment>
+                )
+            })}
             <section aria-label="Filter">
                 <h2>Filter presets</h2>
+                {sources.length > 1 && (
+                    <>
+                        <Button
+                            aria-expanded={false}
+                            onClick={() => setShowSources(true)}
+                        >
+                            Sources:{" "}
+                            {selectedSources.length === 0
+                                ? "All"
+                                : joinString(
+                                      sources
+                                          .filter((s) =>
+                                              selectedSources.includes(s.id),
+                                          )
+                                          .map((s) => s.name),
+                                      ", ",
+                                      " and ",
+                                  )}
+                        </Button>
+                        <Modal
+                            show={showSources}
+                            onHide={() => {
+                                setShowSources(false)
+                                setSelectedSources(temporarilySelectedSources)
+                            }}
+                        >
+                            <Modal.Header closeButton closeLabel="Save">
+                                <Modal.Title>Sources</Modal.Title>
+                            </Modal.Header>
+                            <Modal.Body>
+                                <Button
+                                    onClick={() =>
+                                        setTemporarilySelectedSources([])
+                                    }
+                                >
+                                    Deselect all
+                                </Button>
+                                <div role="list" aria-label="Sources">
+                                    {sources
+                                        .filter((s) => s.error === null)
+                                        .map((s) => (
+                                            <div role="listitem" key={s.id}>
+                                                <Form.Check
+                                                    type="checkbox"
+                                                    id={`source-${s.id}`}
+                                                    label={`${s.name} (${s.presets})`}
+                                                    checked={temporarilySelectedSources.includes(
+                                                        s.id,
+                                                    )}
+                                                    onChange={() =>
+                                                        temporarilySelectedSources.includes(
+                                                            s.id,
+                                                        )
+                                                            ? setTemporarilySelectedSources(
+                                                                  temporarilySelectedSources.filter(
+                                                                      (s2) =>
+                                                                          s.id !==
+                                                                          s2,
+                                                                  ),
+                                                              )
+                                                            : setTemporarilySelectedSources(
+                                                                  [
+                                                                      ...temporarilySelectedSources,
+                                                                      s.id,
+                                                                  ],
+                                                              )
+                                                    }
+                                                />
+                                            </div>
+                                        ))}
+                                </div>
+                            </Modal.Body>
+                        </Modal>
+                    </>
+                )}
                 <Button
                     aria-expanded={false}
                     onClick={() => setShowVendors(true)}
@@ -201,7 +498,9 @@ function Home() {
                     {selectedVendors.length === 0
                         ? "All"
                         : joinString(
-                              selectedVendors.sort(sorter),
+                              selectedVendors
+                                  .map((v) => vendors.get(v)!.name)
+                                  .sort(sorter),
                               ", ",
                               " and ",
                           )}
@@ -223,39 +522,38 @@ function Home() {
                             Deselect all
                         </Button>
                         <div role="list" aria-label="Vendors">
-                            {vendors!
+                            {[...vendors.values()]
                                 .filter(
                                     (v) =>
                                         selectedProducts.length === 0 ||
                                         selectedProducts
                                             .map((p) => products.get(p)!)
-                                            .find((p) => p.vendor === v) !==
-                                            undefined,
+                                            .find(
+                                                (p) => p.vendor_id === v.id,
+                                            ) !== undefined,
                                 )
-                                .sort(sorter)
-                                .map((v, i) => (
-                                    <div role="listitem">
+                                .map((v) => (
+                                    <div role="listitem" key={v.id}>
                                         <Form.Check
                                             type="checkbox"
-                                            id={`${slugify(v)}-${i}`}
-                                            key={`${slugify(v)}-${i}`}
-                                            label={v}
+                                            id={`${slugify(v.name)}-${v.id}`}
+                                            label={v.name}
                                             checked={temporarilySelectedVendors.includes(
-                                                v,
+                                                v.id,
                                             )}
                                             onChange={() =>
                                                 temporarilySelectedVendors.includes(
-                                                    v,
+                                                    v.id,
                                                 )
                                                     ? setTemporarilySelectedVendors(
                                                           temporarilySelectedVendors.filter(
-                                                              (v2) => v !== v2,
+                                                              (v2) => v.id !== v2,
                                                           ),
                                                       )
                                                     : setTemporarilySelectedVendors(
                                                           [
                                                               ...temporarilySelectedVendors,
-                                                              v,
+                                                              v.id,
                                                           ],
                                                       )
                                             }
@@ -276,8 +574,7 @@ function Home() {
                               selectedProducts
                                   .map(
                                       (p) =>
-                                          products.get(p)!.name ||
-                                          "(unnamed product)",
+                                          getProductName(products.get(p)!),
                                   )
                                   .sort(sorter),
                               ", ",
@@ -306,7 +603,11 @@ function Home() {
                                     <Form.Check
                                         type="checkbox"
                                         id={`${slugify(p.name)}-${i}`}
-                                        label={p.name || "(unnamed product)"}
+                                        label={
+                                            p.info?.version
+                                                ? `${getProductName(p)} (${p.info.version})`
+                                                : getProductName(p)
+                                        }
                                         checked={temporarilySelectedProducts.includes(
                                             p.id,
                                         )}
@@ -385,62 +686,71 @@ function Home() {
                                 onChange={(e) => setBankFilter(e.target.value)}
                             />
                         </Form.Group>
-                        <div role="list" aria-label="Banks">
-                            {[...banks!.values()]
-                                .filter(
-                                    (b) =>
-                                        bankFilter.trim() === "" ||
-                                        b.entry1
-                                            .toLowerCase()
-                                            .includes(
-                                                bankFilter.toLowerCase(),
-                                            ) ||
-                                        b.entry2
-                                            .toLowerCase()
-                                            .includes(
-                                                bankFilter.toLowerCase(),
-                                            ) ||
-                                        b.entry3
-                                            .toLowerCase()
-                                            .includes(bankFilter.toLowerCase()),
-                                )
-                                .map((b, i) => (
-                                    <div role="listitem">
-                                        <Form.Check
-                                            type="checkbox"
-                                            id={`${slugify(b.entry1)}-${i}`}
-                                            label={joinString(
-                                                [
-                                                    b.entry1,
-                                                    b.entry2,
-                                                    b.entry3,
-                                                ].filter((b) => b !== ""),
-                                                " / ",
-                                            )}
-                                            checked={temporarilySelectedBanks.includes(
-                                                b.id,
-                                            )}
-                                            onChange={() =>
-                                                temporarilySelectedBanks.includes(
+                        {bankFilter.trim() === "" ? (
+                            <TreeList
+                                label="Banks"
+                                nodes={bankTree}
+                                selected={temporarilySelectedBanks}
+                                onChange={setTemporarilySelectedBanks}
+                            />
+                        ) : (
+                            <div role="list" aria-label="Banks">
+                                {[...banks!.values()]
+                                    .filter(
+                                        (b) =>
+                                            bankFilter.trim() === "" ||
+                                            b.entry1
+                                                .toLowerCase()
+                                                .includes(
+                                                    bankFilter.toLowerCase(),
+                                                ) ||
+                                            b.entry2
+                                                .toLowerCase()
+                                                .includes(
+                                                    bankFilter.toLowerCase(),
+                                                ) ||
+                                            b.entry3
+                                                .toLowerCase()
+                                                .includes(bankFilter.toLowerCase()),
+                                    )
+                                    .map((b, i) => (
+                                        <div role="listitem">
+                                            <Form.Check
+                                                type="checkbox"
+                                                id={`${slugify(b.entry1)}-${i}`}
+                                                label={joinString(
+                                                    [
+                                                        b.entry1,
+                                                        b.entry2,
+                                                        b.entry3,
+                                                    ].filter((b) => b !== ""),
+                                                    " / ",
+                                                )}
+                                                checked={temporarilySelectedBanks.includes(
                                                     b.id,
-                                                )
-                                                    ? setTemporarilySelectedBanks(
-                                                          temporarilySelectedBanks.filter(
-                                                              (b2) =>
-                                                                  b.id !== b2,
-                                                          ),
-                                                      )
-                                                    : setTemporarilySelectedBanks(
-                                                          [
-                                                              ...temporarilySelectedBanks,
-                                                              b.id,
-                                                          ],
-                                                      )
-                                            }
-                                        />
-                                    </div>
-                                ))}
-                        </div>
+                                                )}
+                                                onChange={() =>
+                                                    temporarilySelectedBanks.includes(
+                                                        b.id,
+                                                    )
+                                                        ? setTemporarilySelectedBanks(
+                                                              temporarilySelectedBanks.filter(
+                                                                  (b2) =>
+                                                                      b.id !== b2,
+                                                              ),
+                                                          )
+                                                        : setTemporarilySelectedBanks(
+                                                              [
+                                                                  ...temporarilySelectedBanks,
+                                                                  b.id,
+                                                              ],
+                                                          )
+                                                }
+                                            />
+                                        </div>
+                                    ))}
+                            </div>
+                        )}
                     </Modal.Body>
                 </Modal>
                 <Button
@@ -483,43 +793,12 @@ function Home() {
                         >
                             Deselect all
                         </Button>
-                        <div role="list" aria-label="Types">
-                            {[...categories!.values()].map((c, i) => (
-                                <div role="listitem">
-                                    <Form.Check
-                                        type="checkbox"
-                                        id={`${slugify(c.name)}-${i}`}
-                                        label={joinString(
-                                            [
-                                                c.name,
-                                                c.subcategory,
-                                                c.subsubcategory,
-                                            ].filter((c) => c !== ""),
-                                            " / ",
-                                        )}
-                                        checked={temporarilySelectedCategories.includes(
-                                            c.id,
-                                        )}
-                                        onChange={() =>
-                                            temporarilySelectedCategories.includes(
-                                                c.id,
-                                            )
-                                                ? setTemporarilySelectedCategories(
-                                                      temporarilySelectedCategories.filter(
-                                                          (c2) => c.id !== c2,
-                                                      ),
-                                                  )
-                                                : setTemporarilySelectedCategories(
-                                                      [
-                                                          ...temporarilySelectedCategories,
-                                                          c.id,
-                                                      ],
-                                                  )
-                                        }
-                                    />
-                                </div>
-                            ))}
-                        </div>
+                        <TreeList
+                            label="Types"
+                            nodes={categoryTree}
+                            selected={temporarilySelectedCategories}
+                            onChange={setTemporarilySelectedCategories}
+                        />
                     </Modal.Body>
                 </Modal>
                 <Button
@@ -584,6 +863,13 @@ function Home() {
             </section>
             <section aria-label="Results">
                 <h2>Results</h2>
+                <Form.Check
+                    type="checkbox"
+                    id="hide-broken"
+                    label="Hide presets with missing files"
+                    checked={hideBroken}
+                    onChange={() => setHideBroken(!hideBroken)}
+                />
                 <Select
                     closeMenuOnSelect={false}
                     inputValue={query}
@@ -599,10 +885,13 @@ function Home() {
                         }
                     }}
                     cacheUniqs={[
+                        hideBroken,
+                        refreshing,
                         selectedBanks,
                         selectedCategories,
                         selectedModes,
                         selectedProducts,
+                        selectedSources,
                         selectedVendors,
                     ]}
                     value={selectedPreset}
@@ -615,6 +904,8 @@ function Home() {
                             categories: selectedCategories,
                             modes: selectedModes,
                             banks: selectedBanks,
+                            sources: selectedSources,
+                            includeBroken: !hideBroken,
                             query: query,
                             offset: loadedOptions.length,
                             limit: PAGE_SIZE,
@@ -639,6 +930,36 @@ function Home() {
                     }}
                 />
             </section>
+            {productDetails !== null ? (
+                <section aria-label="Product details">
+                    <h2>
+                        Product details for {getProductName(productDetails)}
+                    </h2>
+                    {productDetails.info?.artwork && (
+                        <img
+                            src={getResourceUrl("artwork", productDetails.id)}
+                            alt={`Artwork of ${getProductName(productDetails)}`}
+                        />
+                    )}
+                    <p>Vendor: {productDetails.vendor}</p>
+                    <p>Presets: {productDetails.presets}</p>
+                    {productDetails.info?.version && (
+                        <p>Version: {productDetails.info.version}</p>
+                    )}
+                    {productDetails.info?.product_type && (
+                        <p>Type: {productDetails.info.product_type}</p>
+                    )}
+                    {productDetails.info?.install_date && (
+                        <p>
+                            Installed:{" "}
+                            {new Date(
+                                productDetails.info.install_date * 1000,
+                            ).toLocaleDateString()}
+                        </p>
+                    )}
+                    <p>Content directory: {productDetails.content_dir}</p>
+                </section>
+            ) : null}
             <section aria-label="Preset details">
                 {selectedPreset === undefined ? (
                     <p>No preset selected</p>
@@ -647,6 +968,21 @@ function Home() {
                         <h2>Preset details for {selectedPreset.name}</h2>
                         <p>Vendor: {selectedPreset.vendor}</p>
                         <p>Product: {selectedPreset.product_name}</p>
+                        <audio
+                            controls
+                            preload="none"
+                            src={getResourceUrl("preview", selectedPreset.id)}
+                            aria-label={`Preview of ${selectedPreset.name}`}
+                        />
+                        {selectedPreset.status === "missing_file" && (
+                            <p>The preset file could not be found.</p>
+                        )}
+                        {selectedPreset.status === "missing_content_dir" && (
+                            <p>
+                                The content directory of this product could not
+                                be found.
+                            </p>
+                        )}
                         <p>
                             Bank:{" "}
                             {selectedPreset.bank === 0
@@ -694,6 +1030,23 @@ function Home() {
                                 " and ",
                             ) || "none"}
                         </p>
+                        {parameterPages.length > 0 && (
+                            <>
+                                <h3>Parameters</h3>
+                                {parameterPages.map((page, i) => (
+                                    <section key={i} aria-label={page.name}>
+                                        <h4>{page.name}</h4>
+                                        <ol>
+                                            {page.knobs.map((k, j) => (
+                                                <li key={j}>
+                                                    {k.name || "unassigned"}
+                                                </li>
+                                            ))}
+                                        </ol>
+                                    </section>
+                                ))}
+                            </>
+                        )}
                         <Button
                             onClick={async () =>
                                 await writeText(selectedPreset!.file_name)
@@ -0,0 +1,78 @@
+import Form from "react-bootstrap/Form"
+import slugify from "slugify"
+
+export interface TreeNode {
+    name: string
+    path: string[]
+    ids: number[]
+    presets: number
+    children: TreeNode[]
+}
+
+interface TreeItemProps {
+    node: TreeNode
+    selected: number[]
+    onChange: (selected: number[]) => void
+}
+
+function TreeItem({ node, selected, onChange }: TreeItemProps) {
+    // a branch counts as selected once all of its entries are
+    const checked = node.ids.every((id) => selected.includes(id))
+
+    return (
+        <li role="treeitem" aria-selected={checked}>
+            <Form.Check
+                type="checkbox"
+                id={slugify(node.path.join("-"))}
+                label={`${node.name} (${node.presets})`}
+                checked={checked}
+                onChange={() =>
+                    checked
+                        ? onChange(
+                              selected.filter((id) => !node.ids.includes(id)),
+                          )
+                        : onChange([
+                              ...selected,
+                              ...node.ids.filter(
+                                  (id) => !selected.includes(id),
+                              ),
+                          ])
+                }
+            />
+            {node.children.length > 0 ? (
+                <ul role="group">
+                    {node.children.map((c) => (
+                        <TreeItem
+                            key={c.path.join("/")}
+                            node={c}
+                            selected={selected}
+                            onChange={onChange}
+                        />
+                    ))}
+                </ul>
+            ) : null}
+        </li>
+    )
+}
+
+interface TreeListProps extends Omit<TreeItemProps, "node"> {
+    label: string
+    nodes: TreeNode[]
+}
+
+function TreeList({ label, nodes, selected, onChange }: TreeListProps) {
+    return (
+        <ul role="tree" aria-label={label}>
+            {nodes.map((n) => (
+                <TreeItem
+                    key={n.path.join("/")}
+                    node={n}
+                    selected={selected}
+                    onChange={onChange}
+                />
+            ))}
+        </ul>
+    )
+}
+
+export default TreeList
@@ -1,3 +1,5 @@
+import { convertFileSrc } from "@tauri-apps/api/core"
+
 export const joinString = (
     s: string[],
     sep: string,
@@ -10,3 +12,9 @@ export const joinString = (
     else if (s.length === 2) return s[0] + final + s[1]
     else return s[0] + sep + joinString(s.slice(1), sep, final)
 }
+
+// files served by the backend's kkb:// protocol, looked up by product or preset id
+export const getResourceUrl = (
+    kind: "artwork" | "preview",
+    id: number,
+): string => convertFileSrc(`${kind}/${id}`, "kkb").replace("%2F", "/")